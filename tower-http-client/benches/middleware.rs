@@ -0,0 +1,98 @@
+//! Benchmarks of the middleware overhead without the network and real waiting.
+
+use std::{
+    convert::Infallible,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, Criterion};
+use futures_util::future::BoxFuture;
+use http::StatusCode;
+use tower::{service_fn, ServiceExt as _};
+use tower_http_client::{clock::Clock, limit::ConcurrencyAndRateLayer, retry::RetryLayer};
+use tower_layer::Layer as _;
+
+// Clock which advances the time on sleep instead of waiting.
+#[derive(Debug, Clone)]
+struct ManualClock {
+    start: Instant,
+    elapsed_nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed_nanos: Arc::default(),
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.elapsed_nanos.load(Ordering::Relaxed))
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + self.elapsed()
+    }
+
+    fn instant(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.elapsed_nanos.fetch_add(nanos, Ordering::Relaxed);
+        Box::pin(std::future::ready(()))
+    }
+}
+
+fn bench_retry(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let calls = Arc::new(AtomicUsize::new(0));
+    // Every third attempt succeeds, so each request is retried twice.
+    let inner = service_fn(move |_request: http::Request<Bytes>| {
+        let status = if calls.fetch_add(1, Ordering::Relaxed) % 3 == 2 {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        async move {
+            let mut response = http::Response::new(());
+            *response.status_mut() = status;
+            Ok::<_, Infallible>(response)
+        }
+    });
+    let service = RetryLayer::new().clock(ManualClock::new()).layer(inner);
+
+    c.bench_function("retry_two_attempts", |b| {
+        b.to_async(&runtime).iter(|| {
+            service
+                .clone()
+                .oneshot(http::Request::new(Bytes::from_static(b"hello")))
+        });
+    });
+}
+
+fn bench_concurrency_and_rate(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let inner = service_fn(|request: u32| async move { Ok::<_, Infallible>(request) });
+    // Each request past the first one in the interval waits for the rate.
+    let service = ConcurrencyAndRateLayer::new(64, 1, Duration::from_secs(1))
+        .queue(1)
+        .clock(ManualClock::new())
+        .layer(inner);
+
+    c.bench_function("concurrency_and_rate", |b| {
+        b.to_async(&runtime).iter(|| service.clone().oneshot(1));
+    });
+}
+
+criterion_group!(benches, bench_retry, bench_concurrency_and_rate);
+criterion_main!(benches);
@@ -5,5 +5,15 @@
 /// [`reqwest`]: https://crates.io/crates/reqwest
 #[cfg(feature = "reqwest")]
 pub mod reqwest {
-    pub use tower_reqwest::{error, into_reqwest_body, HttpClientLayer, HttpClientService};
+    pub use tower_reqwest::{
+        error, into_reqwest_body, into_reqwest_body_sized, HttpClientLayer, HttpClientService,
+    };
+}
+
+/// Adapter for [`hyper`] client.
+///
+/// [`hyper`]: https://crates.io/crates/hyper
+#[cfg(feature = "hyper")]
+pub mod hyper {
+    pub use tower_reqwest::{error, HttpClientLayer, HttpClientService, HyperClient};
 }
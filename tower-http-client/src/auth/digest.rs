@@ -0,0 +1,534 @@
+//! HTTP Digest access authentication ([RFC 7616]).
+//!
+//! [RFC 7616]: https://www.rfc-editor.org/rfc/rfc7616
+
+use std::{
+    fmt::{self, Write as _},
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll},
+};
+
+use futures_util::future::BoxFuture;
+use http::{
+    header::{AUTHORIZATION, WWW_AUTHENTICATE},
+    HeaderValue, Method, StatusCode, Uri,
+};
+use md5::Md5;
+use sha2::{Digest as _, Sha256};
+use thiserror::Error;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{
+    client::{try_clone::try_clone_request, TryCloneBody},
+    random::random_u64,
+    BoxError,
+};
+
+/// The original request cannot be replayed with the `Authorization` header,
+/// because its body cannot be cloned.
+#[derive(Debug, Error)]
+#[error("digest authentication requires replaying the request, but its body cannot be cloned")]
+pub struct BodyNotCloneableError;
+
+/// Layer that applies the [`AddDigestAuthorization`] middleware.
+#[derive(Debug, Clone)]
+pub struct AddDigestAuthorizationLayer {
+    credentials: Arc<Credentials>,
+}
+
+impl AddDigestAuthorizationLayer {
+    /// Creates a new layer with the given credentials.
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            credentials: Arc::new(Credentials {
+                username: username.into(),
+                password: password.into(),
+            }),
+        }
+    }
+}
+
+impl<S> Layer<S> for AddDigestAuthorizationLayer {
+    type Service = AddDigestAuthorization<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AddDigestAuthorization {
+            inner,
+            credentials: self.credentials.clone(),
+            state: Arc::default(),
+        }
+    }
+}
+
+/// Middleware that authorizes requests using the HTTP Digest authentication scheme.
+///
+/// On receiving a `401 Unauthorized` response with a `WWW-Authenticate: Digest` challenge,
+/// it computes the digest response and replays the request with the proper `Authorization`
+/// header. The last received challenge is reused for the subsequent requests, so that
+/// the nonce count is incremented correctly. The state is shared between the clones
+/// of the service.
+///
+/// The supported algorithms are `MD5`, `SHA-256` and their `-sess` variants with
+/// the `auth` quality of protection.
+///
+/// # Request body
+///
+/// Since digest authentication is a challenge-response scheme, the original request has to be
+/// replayed, so its body must implement the [`TryCloneBody`] trait. If the body cannot be
+/// cloned the request is sent as is, and if the server asks for authentication, the
+/// [`BodyNotCloneableError`] is returned.
+///
+/// # Example
+///
+/// ```
+/// use tower::ServiceBuilder;
+/// use tower_http_client::auth::AddDigestAuthorizationLayer;
+/// use tower_reqwest::HttpClientLayer;
+///
+/// let client = ServiceBuilder::new()
+///     .layer(AddDigestAuthorizationLayer::new("Mufasa", "Circle of Life"))
+///     .layer(HttpClientLayer)
+///     .service(reqwest::Client::new());
+/// ```
+#[derive(Clone)]
+pub struct AddDigestAuthorization<S> {
+    inner: S,
+    credentials: Arc<Credentials>,
+    state: Arc<Mutex<Option<ChallengeState>>>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for AddDigestAuthorization<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AddDigestAuthorization")
+            .field("inner", &self.inner)
+            .field("credentials", &self.credentials)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> AddDigestAuthorization<S> {
+    // Adds the `Authorization` header using the last received challenge, if any.
+    fn authorize<B>(&self, request: &mut http::Request<B>) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(state) = state.as_mut() {
+            let value =
+                state.next_authorization(&self.credentials, request.method(), request.uri());
+            if let Some(value) = value {
+                request.headers_mut().insert(AUTHORIZATION, value);
+            }
+        }
+    }
+
+    // Stores the new challenge and returns `true` if it is supported.
+    fn update_challenge(&self, response: &http::HeaderMap) -> bool {
+        let Some(challenge) = response
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(Challenge::parse)
+            .max_by_key(|challenge| challenge.algorithm)
+        else {
+            return false;
+        };
+
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        *state = Some(ChallengeState {
+            challenge,
+            nonce_count: 0,
+        });
+        true
+    }
+}
+
+impl<S, ReqBody, RespBody> Service<http::Request<ReqBody>> for AddDigestAuthorization<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<RespBody>>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+    ReqBody: TryCloneBody + Send + 'static,
+    RespBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut request: http::Request<ReqBody>) -> Self::Future {
+        // Take the service that was ready.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let this = Self {
+            inner: self.inner.clone(),
+            credentials: self.credentials.clone(),
+            state: self.state.clone(),
+        };
+
+        Box::pin(async move {
+            this.authorize(&mut request);
+            let replay = try_clone_request(&request);
+
+            let response = inner.call(request).await.map_err(Into::into)?;
+            if response.status() != StatusCode::UNAUTHORIZED
+                || !this.update_challenge(response.headers())
+            {
+                return Ok(response);
+            }
+
+            let mut request = replay.ok_or(BodyNotCloneableError)?;
+            this.authorize(&mut request);
+
+            let mut inner = this.inner;
+            futures_util::future::poll_fn(|cx| inner.poll_ready(cx))
+                .await
+                .map_err(Into::into)?;
+            inner.call(request).await.map_err(Into::into)
+        })
+    }
+}
+
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Algorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl Algorithm {
+    fn parse(value: &str) -> Option<Self> {
+        [
+            ("MD5", Self::Md5),
+            ("MD5-sess", Self::Md5Sess),
+            ("SHA-256", Self::Sha256),
+            ("SHA-256-sess", Self::Sha256Sess),
+        ]
+        .into_iter()
+        .find_map(|(name, algorithm)| name.eq_ignore_ascii_case(value).then_some(algorithm))
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Md5Sess => "MD5-sess",
+            Self::Sha256 => "SHA-256",
+            Self::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    const fn is_session(self) -> bool {
+        matches!(self, Self::Md5Sess | Self::Sha256Sess)
+    }
+
+    fn hash(self, data: &str) -> String {
+        let digest = match self {
+            Self::Md5 | Self::Md5Sess => Md5::digest(data).to_vec(),
+            Self::Sha256 | Self::Sha256Sess => Sha256::digest(data).to_vec(),
+        };
+        digest.iter().fold(String::new(), |mut out, byte| {
+            let _ = write!(out, "{byte:02x}");
+            out
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Challenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: Algorithm,
+    // Whether the `auth` quality of protection is offered.
+    qop_auth: bool,
+}
+
+impl Challenge {
+    fn parse(value: &str) -> Option<Self> {
+        let (scheme, params) = value.trim().split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("Digest") {
+            return None;
+        }
+
+        let mut challenge = Self {
+            realm: String::new(),
+            nonce: String::new(),
+            opaque: None,
+            algorithm: Algorithm::Md5,
+            qop_auth: false,
+        };
+        let mut has_nonce = false;
+        for (key, value) in parse_params(params) {
+            match key.to_ascii_lowercase().as_str() {
+                "realm" => challenge.realm = value,
+                "nonce" => {
+                    challenge.nonce = value;
+                    has_nonce = true;
+                }
+                "opaque" => challenge.opaque = Some(value),
+                "algorithm" => challenge.algorithm = Algorithm::parse(&value)?,
+                "qop" => {
+                    challenge.qop_auth = value
+                        .split(',')
+                        .any(|qop| qop.trim().eq_ignore_ascii_case("auth"));
+                }
+                _ => {}
+            }
+        }
+        has_nonce.then_some(challenge)
+    }
+}
+
+#[derive(Debug)]
+struct ChallengeState {
+    challenge: Challenge,
+    nonce_count: u32,
+}
+
+impl ChallengeState {
+    fn next_authorization(
+        &mut self,
+        credentials: &Credentials,
+        method: &Method,
+        uri: &Uri,
+    ) -> Option<HeaderValue> {
+        self.nonce_count += 1;
+        let cnonce = generate_cnonce();
+        let uri = uri.path_and_query().map_or("/", |path| path.as_str());
+        authorization(
+            &self.challenge,
+            credentials,
+            method,
+            uri,
+            self.nonce_count,
+            &cnonce,
+        )
+    }
+}
+
+// Computes the `Authorization` header value for the given challenge.
+//
+// Returns `None` if the credentials cannot be represented as a header value.
+fn authorization(
+    challenge: &Challenge,
+    credentials: &Credentials,
+    method: &Method,
+    uri: &str,
+    nonce_count: u32,
+    cnonce: &str,
+) -> Option<HeaderValue> {
+    let algorithm = challenge.algorithm;
+    let nc = format!("{nonce_count:08x}");
+
+    let mut ha1 = algorithm.hash(&format!(
+        "{}:{}:{}",
+        credentials.username, challenge.realm, credentials.password
+    ));
+    if algorithm.is_session() {
+        ha1 = algorithm.hash(&format!("{ha1}:{}:{cnonce}", challenge.nonce));
+    }
+    let ha2 = algorithm.hash(&format!("{method}:{uri}"));
+    let response = if challenge.qop_auth {
+        algorithm.hash(&format!(
+            "{ha1}:{}:{nc}:{cnonce}:auth:{ha2}",
+            challenge.nonce
+        ))
+    } else {
+        algorithm.hash(&format!("{ha1}:{}:{ha2}", challenge.nonce))
+    };
+
+    let mut value = format!(
+        "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{uri}\", algorithm={}, response=\"{response}\"",
+        escape(&credentials.username),
+        escape(&challenge.realm),
+        escape(&challenge.nonce),
+        algorithm.name(),
+    );
+    if challenge.qop_auth {
+        let _ = write!(value, ", qop=auth, nc={nc}, cnonce=\"{cnonce}\"");
+    }
+    if let Some(opaque) = &challenge.opaque {
+        let _ = write!(value, ", opaque=\"{}\"", escape(opaque));
+    }
+
+    let mut value = HeaderValue::try_from(value).ok()?;
+    value.set_sensitive(true);
+    Some(value)
+}
+
+// Parses comma-separated `key=value` pairs, where values can be quoted strings.
+fn parse_params(input: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut chars = input.chars().peekable();
+    loop {
+        // Skip separators.
+        while chars.next_if(|c| *c == ',' || c.is_whitespace()).is_some() {}
+
+        let key: String =
+            std::iter::from_fn(|| chars.next_if(|c| *c != '=' && *c != ',')).collect();
+        if key.is_empty() {
+            break;
+        }
+        if chars.next_if_eq(&'=').is_none() {
+            continue;
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+        } else {
+            value.extend(std::iter::from_fn(|| chars.next_if(|c| *c != ',')));
+        }
+        params.push((key.trim().to_owned(), value.trim().to_owned()));
+    }
+    params
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn generate_cnonce() -> String {
+    // The client nonce protects against the chosen plaintext attacks, it is not a secret.
+    format!("{:016x}{:016x}", random_u64(), random_u64())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use http::{
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+        Method, StatusCode,
+    };
+    use pretty_assertions::assert_eq;
+    use tower::{ServiceBuilder, ServiceExt as _};
+
+    use super::{authorization, parse_params, AddDigestAuthorizationLayer, Challenge, Credentials};
+
+    const CHALLENGE: &str = r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm=SHA-256, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#;
+
+    fn credentials() -> Credentials {
+        Credentials {
+            username: "Mufasa".to_owned(),
+            password: "Circle of Life".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_digest_parse_params() {
+        let params = parse_params(r#"realm="a \"b\", c", qop=auth, nonce="xyz""#);
+        assert_eq!(
+            params,
+            [
+                ("realm".to_owned(), "a \"b\", c".to_owned()),
+                ("qop".to_owned(), "auth".to_owned()),
+                ("nonce".to_owned(), "xyz".to_owned()),
+            ]
+        );
+    }
+
+    // Examples from the RFC 7616, section 3.9.1.
+    #[test]
+    fn test_digest_rfc_7616_examples() {
+        let cnonce = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+
+        let challenge = Challenge::parse(CHALLENGE).unwrap();
+        let value = authorization(
+            &challenge,
+            &credentials(),
+            &Method::GET,
+            "/dir/index.html",
+            1,
+            cnonce,
+        )
+        .unwrap();
+        assert!(value.is_sensitive());
+        assert!(value.to_str().unwrap().contains(
+            r#"response="753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1""#
+        ));
+
+        let challenge = Challenge::parse(&CHALLENGE.replace("SHA-256", "MD5")).unwrap();
+        let value = authorization(
+            &challenge,
+            &credentials(),
+            &Method::GET,
+            "/dir/index.html",
+            1,
+            cnonce,
+        )
+        .unwrap();
+        assert!(value
+            .to_str()
+            .unwrap()
+            .contains(r#"response="8ca523f5e9506fed4657c9700eebdbec""#));
+    }
+
+    #[tokio::test]
+    async fn test_digest_authorization_service() -> anyhow::Result<()> {
+        let authorizations = Arc::new(Mutex::new(Vec::new()));
+        let service = tower::service_fn({
+            let authorizations = authorizations.clone();
+            move |request: http::Request<String>| {
+                let authorization = request.headers().get(AUTHORIZATION).cloned();
+                let response = if let Some(authorization) = authorization {
+                    authorizations
+                        .lock()
+                        .unwrap()
+                        .push(authorization.to_str().unwrap().to_owned());
+                    http::Response::new(String::new())
+                } else {
+                    http::Response::builder()
+                        .status(StatusCode::UNAUTHORIZED)
+                        .header(WWW_AUTHENTICATE, r#"Basic realm="other""#)
+                        .header(WWW_AUTHENTICATE, CHALLENGE)
+                        .body(String::new())
+                        .unwrap()
+                };
+                async move { Ok::<_, std::convert::Infallible>(response) }
+            }
+        });
+        let client = ServiceBuilder::new()
+            .layer(AddDigestAuthorizationLayer::new("Mufasa", "Circle of Life"))
+            .service(service);
+
+        for _ in 0..2 {
+            let request = http::Request::get("/dir/index.html").body(String::new())?;
+            let response = client.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let authorizations = authorizations.lock().unwrap();
+        assert_eq!(authorizations.len(), 2);
+        assert!(authorizations[0].contains("algorithm=SHA-256"));
+        assert!(authorizations[0].contains("nc=00000001"));
+        assert!(authorizations[1].contains("nc=00000002"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+//! Authorization middlewares for HTTP clients.
+//!
+//! The static basic or bearer credentials are set by the
+//! [`tower_http::auth::AddAuthorizationLayer`] middleware, which is generic over the request
+//! body, so it can be placed above the `HttpClientLayer` adapter as well.
+//!
+//! [`tower_http::auth::AddAuthorizationLayer`]: https://docs.rs/tower-http/latest/tower_http/auth/add_authorization/struct.AddAuthorizationLayer.html
+
+#[cfg(feature = "digest")]
+pub use self::digest::{
+    AddDigestAuthorization, AddDigestAuthorizationLayer, BodyNotCloneableError,
+};
+
+#[cfg(feature = "digest")]
+pub mod digest;
@@ -0,0 +1,253 @@
+//! Middleware that resolves relative request URIs against a base one.
+//!
+//! It lets to build a client once against a known API host and then send requests with
+//! the relative paths only:
+//!
+//! - The relative request path is appended to the base path prefix, so the base
+//!   `http://api.example.com/v1/` and the request `/users?page=2` are resolved into
+//!   `http://api.example.com/v1/users?page=2`.
+//! - The request URIs with the scheme and authority are passed through untouched.
+//! - The request URIs with the authority but without the scheme, like `example.com/users`,
+//!   cannot be resolved and fail with the [`ResolveUriError`]. Note that relative paths
+//!   should start with the slash, since otherwise their first segment is parsed as
+//!   the authority.
+//!
+//! The middleware operates on the [`http::Request`] level, so it can be placed above
+//! the client adapter.
+//!
+//! # Example
+//!
+//! ```
+//! use http::Uri;
+//! use tower::ServiceBuilder;
+//! use tower_http_client::base_uri::BaseUriLayer;
+//! use tower_reqwest::HttpClientLayer;
+//!
+//! let client = ServiceBuilder::new()
+//!     .layer(BaseUriLayer::new(Uri::from_static("http://api.example.com/v1")))
+//!     .layer(HttpClientLayer)
+//!     .service(reqwest::Client::new());
+//! ```
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use http::{uri::PathAndQuery, Request, Uri};
+use pin_project::pin_project;
+use thiserror::Error;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::BoxError;
+
+/// The request URI cannot be resolved against the base one.
+#[derive(Debug, Error)]
+#[error("failed to resolve `{uri}` against the base URI `{base}`")]
+pub struct ResolveUriError {
+    /// The base URI.
+    pub base: Uri,
+    /// The relative request URI.
+    pub uri: Uri,
+    #[source]
+    source: Option<http::Error>,
+}
+
+/// Layer that applies the [`BaseUri`] middleware.
+#[derive(Debug, Clone)]
+pub struct BaseUriLayer {
+    base: Uri,
+}
+
+impl BaseUriLayer {
+    /// Creates a new layer with the given base URI.
+    ///
+    /// The query of the base URI is ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the base URI has no scheme or authority.
+    #[must_use]
+    pub fn new(base: Uri) -> Self {
+        assert!(
+            base.scheme().is_some() && base.authority().is_some(),
+            "base URI should have the scheme and authority"
+        );
+        Self { base }
+    }
+}
+
+impl<S> Layer<S> for BaseUriLayer {
+    type Service = BaseUri<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BaseUri {
+            inner,
+            base: self.base.clone(),
+        }
+    }
+}
+
+/// Middleware that resolves relative request URIs against a base one.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct BaseUri<S> {
+    inner: S,
+    base: Uri,
+}
+
+impl<S> BaseUri<S> {
+    /// Creates a new middleware with the given base URI.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the base URI has no scheme or authority.
+    pub fn new(inner: S, base: Uri) -> Self {
+        BaseUriLayer::new(base).layer(inner)
+    }
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for BaseUri<S>
+where
+    S: Service<Request<ReqBody>>,
+    S::Error: Into<BoxError>,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        if req.uri().scheme().is_none() {
+            match resolve(&self.base, req.uri()) {
+                Ok(uri) => *req.uri_mut() = uri,
+                Err(source) => {
+                    let error = ResolveUriError {
+                        base: self.base.clone(),
+                        uri: req.uri().clone(),
+                        source,
+                    };
+                    return ResponseFuture::Error {
+                        error: Some(error.into()),
+                    };
+                }
+            }
+        }
+
+        ResponseFuture::Future {
+            fut: self.inner.call(req),
+        }
+    }
+}
+
+/// Response future for the [`BaseUri`] middleware.
+#[pin_project(project = ResponseFutureProj)]
+#[derive(Debug)]
+pub enum ResponseFuture<F> {
+    #[doc(hidden)]
+    Future {
+        #[pin]
+        fut: F,
+    },
+    #[doc(hidden)]
+    Error { error: Option<BoxError> },
+}
+
+impl<F, T, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Into<BoxError>,
+{
+    type Output = Result<T, BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project() {
+            ResponseFutureProj::Future { fut } => fut.poll(cx).map_err(Into::into),
+            ResponseFutureProj::Error { error } => {
+                let error = error.take().expect("Polled after ready");
+                Poll::Ready(Err(error))
+            }
+        }
+    }
+}
+
+// Joins the base path prefix and the relative request path without duplicating slashes.
+fn resolve(base: &Uri, uri: &Uri) -> Result<Uri, Option<http::Error>> {
+    if uri.authority().is_some() {
+        return Err(None);
+    }
+
+    let prefix = base.path().trim_end_matches('/');
+    let path = uri.path().trim_start_matches('/');
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{prefix}/{path}?{query}"),
+        None => format!("{prefix}/{path}"),
+    };
+
+    let mut parts = base.clone().into_parts();
+    parts.path_and_query =
+        Some(PathAndQuery::try_from(path_and_query).map_err(|err| Some(http::Error::from(err)))?);
+    Uri::from_parts(parts).map_err(|err| Some(err.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use http::{Request, Response, Uri};
+    use tower::{service_fn, ServiceExt as _};
+
+    use super::{resolve, BaseUri, ResolveUriError};
+
+    #[test]
+    fn test_base_uri_resolve() {
+        let cases = [
+            ("http://localhost", "/users", "http://localhost/users"),
+            ("http://localhost/", "/users", "http://localhost/users"),
+            ("http://localhost/v1", "/users", "http://localhost/v1/users"),
+            (
+                "http://localhost/v1/",
+                "/users?page=2",
+                "http://localhost/v1/users?page=2",
+            ),
+            ("http://localhost/v1?key=1", "/", "http://localhost/v1/"),
+        ];
+        for (base, uri, expected) in cases {
+            let resolved = resolve(&Uri::from_static(base), &Uri::from_static(uri)).unwrap();
+            assert_eq!(resolved, expected, "{base} + {uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_base_uri() {
+        let service = BaseUri::new(
+            service_fn(|request: Request<()>| async move {
+                Ok::<_, Infallible>(Response::new(request.uri().to_string()))
+            }),
+            Uri::from_static("http://localhost/v1"),
+        );
+
+        let request = Request::builder().uri("/users").body(()).unwrap();
+        let response = service.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.body(), "http://localhost/v1/users");
+
+        // Absolute URIs are passed through untouched.
+        let request = Request::builder()
+            .uri("https://example.com/users")
+            .body(())
+            .unwrap();
+        let response = service.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.body(), "https://example.com/users");
+
+        // An authority without the scheme cannot be resolved.
+        let request = Request::builder().uri("example.com").body(()).unwrap();
+        let error = service.oneshot(request).await.unwrap_err();
+        assert!(error.is::<ResolveUriError>());
+    }
+}
@@ -0,0 +1,198 @@
+//! Middleware that sets the browser-like request headers.
+//!
+//! Some servers serve different content or reject the requests which don't look like the
+//! browser ones. This middleware sets a coherent bundle of the modern browser headers,
+//! like the `Sec-Fetch-*` ones, according to the chosen [`Preset`]. The headers already
+//! present in the request are left untouched, so the preset values can be overridden per
+//! request.
+//!
+//! Note that the `User-Agent` header is not set, since it should be chosen consistently
+//! with the rest of the client configuration.
+//!
+//! # Example
+//!
+//! ```
+//! use tower::ServiceBuilder;
+//! use tower_http_client::browser_preset::{BrowserPresetLayer, Preset};
+//! use tower_reqwest::HttpClientLayer;
+//!
+//! let client = ServiceBuilder::new()
+//!     .layer(BrowserPresetLayer::new(Preset::ChromeNavigation))
+//!     .layer(HttpClientLayer)
+//!     .service(reqwest::Client::new());
+//! ```
+
+use std::task::{Context, Poll};
+
+use http::{HeaderName, HeaderValue, Request};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// The bundle of the headers set by the [`BrowserPreset`] middleware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Preset {
+    /// Top-level page navigation typed into the Chrome address bar.
+    ///
+    /// Sets the following headers:
+    ///
+    /// - `Accept: text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8`
+    /// - `Accept-Language: en-US,en;q=0.9`
+    /// - `Sec-Fetch-Dest: document`
+    /// - `Sec-Fetch-Mode: navigate`
+    /// - `Sec-Fetch-Site: none`
+    /// - `Sec-Fetch-User: ?1`
+    /// - `Upgrade-Insecure-Requests: 1`
+    ChromeNavigation,
+    /// Cross-origin `fetch()` call made by a page script.
+    ///
+    /// Sets the following headers:
+    ///
+    /// - `Accept: */*`
+    /// - `Accept-Language: en-US,en;q=0.9`
+    /// - `Sec-Fetch-Dest: empty`
+    /// - `Sec-Fetch-Mode: cors`
+    /// - `Sec-Fetch-Site: cross-site`
+    FetchCors,
+}
+
+impl Preset {
+    /// Returns the header names and values set by this preset.
+    #[must_use]
+    pub fn headers(self) -> Vec<(HeaderName, HeaderValue)> {
+        let headers: &[(&'static str, &'static str)] = match self {
+            Self::ChromeNavigation => &[
+                (
+                    "accept",
+                    "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,\
+                     image/webp,image/apng,*/*;q=0.8",
+                ),
+                ("accept-language", "en-US,en;q=0.9"),
+                ("sec-fetch-dest", "document"),
+                ("sec-fetch-mode", "navigate"),
+                ("sec-fetch-site", "none"),
+                ("sec-fetch-user", "?1"),
+                ("upgrade-insecure-requests", "1"),
+            ],
+            Self::FetchCors => &[
+                ("accept", "*/*"),
+                ("accept-language", "en-US,en;q=0.9"),
+                ("sec-fetch-dest", "empty"),
+                ("sec-fetch-mode", "cors"),
+                ("sec-fetch-site", "cross-site"),
+            ],
+        };
+        headers
+            .iter()
+            .map(|&(name, value)| {
+                (
+                    HeaderName::from_static(name),
+                    HeaderValue::from_static(value),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Layer that applies the [`BrowserPreset`] middleware.
+#[derive(Debug, Clone)]
+pub struct BrowserPresetLayer {
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl BrowserPresetLayer {
+    /// Creates a new layer with the given preset.
+    #[must_use]
+    pub fn new(preset: Preset) -> Self {
+        Self {
+            headers: preset.headers(),
+        }
+    }
+}
+
+impl<S> Layer<S> for BrowserPresetLayer {
+    type Service = BrowserPreset<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BrowserPreset {
+            inner,
+            headers: self.headers.clone(),
+        }
+    }
+}
+
+/// Middleware that sets the browser-like request headers.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct BrowserPreset<S> {
+    inner: S,
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl<S> BrowserPreset<S> {
+    /// Creates a new middleware with the given preset.
+    pub fn new(inner: S, preset: Preset) -> Self {
+        BrowserPresetLayer::new(preset).layer(inner)
+    }
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for BrowserPreset<S>
+where
+    S: Service<Request<ReqBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        let headers = request.headers_mut();
+        for (name, value) in &self.headers {
+            if !headers.contains_key(name) {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use http::{HeaderMap, Request, Response};
+    use tower::{service_fn, ServiceExt as _};
+
+    use super::{BrowserPreset, Preset};
+
+    #[tokio::test]
+    async fn test_browser_preset() {
+        let inner = service_fn(|request: Request<()>| async move {
+            Ok::<_, Infallible>(Response::new(request.headers().clone()))
+        });
+        let get = |headers: &HeaderMap, name: &str| headers[name].to_str().unwrap().to_owned();
+
+        let service = BrowserPreset::new(inner, Preset::ChromeNavigation);
+        let response = service.oneshot(Request::new(())).await.unwrap();
+        let headers = response.body();
+        assert_eq!(headers.len(), 7);
+        assert_eq!(get(headers, "sec-fetch-mode"), "navigate");
+        assert_eq!(get(headers, "upgrade-insecure-requests"), "1");
+
+        // The explicit request headers are preserved.
+        let service = BrowserPreset::new(inner, Preset::FetchCors);
+        let request = Request::builder()
+            .header("accept", "application/json")
+            .body(())
+            .unwrap();
+        let response = service.oneshot(request).await.unwrap();
+        let headers = response.body();
+        assert_eq!(headers.len(), 5);
+        assert_eq!(get(headers, "accept"), "application/json");
+        assert_eq!(get(headers, "sec-fetch-site"), "cross-site");
+    }
+}
@@ -0,0 +1,189 @@
+//! Middleware that warns about inconsistent `Accept` and `Content-Type` headers.
+//!
+//! A frequent mistake is sending a JSON body without asking the server for a JSON response
+//! and then getting an HTML page back. This middleware inspects the headers of requests
+//! and responses and emits [`tracing`] warnings in the following cases:
+//!
+//! - A request has a JSON body, but its `Accept` header doesn't allow JSON responses.
+//! - A request has a JSON body or explicitly accepts JSON, but the response `Content-Type`
+//!   is not JSON, so reading it via the [`BodyReader::json`] method will likely fail.
+//!
+//! The middleware never modifies requests or responses, so it is suitable for use in
+//! development builds as a guardrail.
+//!
+//! # Example
+//!
+//! ```
+//! use tower::ServiceBuilder;
+//! use tower_http_client::check_content_type::CheckContentTypeLayer;
+//! use tower_reqwest::HttpClientLayer;
+//!
+//! let client = ServiceBuilder::new()
+//!     .layer(CheckContentTypeLayer)
+//!     .layer(HttpClientLayer)
+//!     .service(reqwest::Client::new());
+//! ```
+//!
+//! [`BodyReader::json`]: crate::client::BodyReader
+//! [`tracing`]: https://docs.rs/tracing
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use http::{
+    header::{ACCEPT, CONTENT_TYPE},
+    HeaderValue, Method, Request, Response, Uri,
+};
+use pin_project::pin_project;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::mime::{is_json, is_json_media_type, media_type};
+
+/// Layer that applies the [`CheckContentType`] middleware.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CheckContentTypeLayer;
+
+impl<S> Layer<S> for CheckContentTypeLayer {
+    type Service = CheckContentType<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CheckContentType { inner }
+    }
+}
+
+/// Middleware that warns about inconsistent `Accept` and `Content-Type` headers.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct CheckContentType<S> {
+    inner: S,
+}
+
+impl<S> CheckContentType<S> {
+    /// Creates a new middleware wrapping the given service.
+    pub const fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S, ReqBody, RespBody> Service<Request<ReqBody>> for CheckContentType<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<RespBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let sends_json = req.headers().get(CONTENT_TYPE).is_some_and(is_json);
+        let accept = req.headers().get(ACCEPT);
+
+        if sends_json && !accept.is_some_and(accepts_json) {
+            tracing::warn!(
+                method = %req.method(),
+                uri = %req.uri(),
+                "request has a JSON body, but its `Accept` header doesn't allow JSON responses"
+            );
+        }
+
+        let expects_json = sends_json || accept.is_some_and(is_json_accept);
+        let request = expects_json.then(|| (req.method().clone(), req.uri().clone()));
+        ResponseFuture {
+            inner: self.inner.call(req),
+            request,
+        }
+    }
+}
+
+/// Response future for the [`CheckContentType`] middleware.
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    // The request method and URI, if the JSON response is expected.
+    request: Option<(Method, Uri)>,
+}
+
+impl<F, RespBody, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<RespBody>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = std::task::ready!(this.inner.poll(cx));
+
+        if let (Ok(response), Some((method, uri))) = (&result, this.request.take()) {
+            if let Some(content_type) = response.headers().get(CONTENT_TYPE) {
+                if !is_json(content_type) {
+                    tracing::warn!(
+                        %method,
+                        %uri,
+                        status = %response.status(),
+                        content_type = ?content_type,
+                        "JSON response is expected, but the response `Content-Type` is not JSON"
+                    );
+                }
+            }
+        }
+        Poll::Ready(result)
+    }
+}
+
+// Checks that the `Accept` header value explicitly lists JSON.
+fn is_json_accept(value: &HeaderValue) -> bool {
+    value.to_str().is_ok_and(|value| {
+        value
+            .split(',')
+            .any(|range| is_json_media_type(media_type(range)))
+    })
+}
+
+// Checks that the `Accept` header value allows JSON responses.
+fn accepts_json(value: &HeaderValue) -> bool {
+    value.to_str().is_ok_and(|value| {
+        value.split(',').any(|range| {
+            let range = media_type(range);
+            range == "*/*"
+                || range.eq_ignore_ascii_case("application/*")
+                || is_json_media_type(range)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::{accepts_json, is_json, is_json_accept};
+
+    #[test]
+    fn test_check_content_type_predicates() {
+        assert!(is_json(&HeaderValue::from_static("application/json")));
+        assert!(is_json(&HeaderValue::from_static(
+            "application/problem+json; charset=utf-8"
+        )));
+        assert!(!is_json(&HeaderValue::from_static("text/html")));
+
+        assert!(accepts_json(&HeaderValue::from_static("*/*")));
+        assert!(accepts_json(&HeaderValue::from_static(
+            "text/html, application/*;q=0.8"
+        )));
+        assert!(!accepts_json(&HeaderValue::from_static("text/html")));
+
+        assert!(is_json_accept(&HeaderValue::from_static(
+            "text/plain, application/json"
+        )));
+        assert!(!is_json_accept(&HeaderValue::from_static("*/*")));
+    }
+}
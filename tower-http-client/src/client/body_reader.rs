@@ -1,8 +1,9 @@
 //! Convenient wrapper for reading [`Body`] content.
 
-use std::string::FromUtf8Error;
+use std::{convert::Infallible, string::FromUtf8Error};
 
-use bytes::{Buf, Bytes};
+use bytes::{Buf, Bytes, BytesMut};
+use futures_util::{Stream, StreamExt as _};
 use http_body::Body;
 use http_body_util::BodyExt;
 use thiserror::Error;
@@ -15,12 +16,44 @@ pub struct BodyReader<B>(B);
 
 /// Read body errors.
 #[derive(Debug, Error)]
-#[error(transparent)]
+#[non_exhaustive]
 pub enum BodyReaderError<E, D> {
     /// An error occurred while reading the body.
+    #[error(transparent)]
     Read(E),
     /// An error occurred while decoding the body content.
+    #[error(transparent)]
     Decode(D),
+    /// The body is larger than the limit set by the [`BodyReader::limited`] method.
+    #[error("body is larger than the limit of {limit} bytes")]
+    TooLarge {
+        /// The maximum allowed body length in bytes.
+        limit: usize,
+    },
+}
+
+/// The `Content-Type` header specifies an unknown charset.
+#[cfg(feature = "charset")]
+#[cfg_attr(docsrs, doc(cfg(feature = "charset")))]
+#[derive(Debug, Error)]
+#[error("unknown charset: {label}")]
+pub struct UnknownCharsetError {
+    /// The charset label.
+    pub label: String,
+}
+
+/// The body checksum doesn't match the one advertised by the response headers.
+#[cfg(feature = "digest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{algorithm} checksum mismatch: expected {expected}, got {actual}")]
+pub struct ChecksumMismatch {
+    /// The checksum algorithm, `md5` or `sha-256`.
+    pub algorithm: &'static str,
+    /// The advertised base64-encoded checksum.
+    pub expected: String,
+    /// The base64-encoded checksum of the received body.
+    pub actual: String,
 }
 
 impl<B> BodyReader<B> {
@@ -29,6 +62,82 @@ impl<B> BodyReader<B> {
         Self(body)
     }
 
+    /// Limits the number of bytes that can be read from the body.
+    ///
+    /// The returned reader accumulates the body frames and stops reading with the
+    /// [`BodyReaderError::TooLarge`] error as soon as the read data exceeds the limit.
+    /// If the body size hint already exceeds the limit, the reading fails immediately.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use http_body_util::Full;
+    /// use tower_http_client::client::{body_reader::BodyReaderError, BodyReader};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let body = Full::new("Hello world".as_bytes());
+    ///     let error = BodyReader::new(body).limited(5).utf8().await.unwrap_err();
+    ///
+    ///     assert!(matches!(error, BodyReaderError::TooLarge { limit: 5 }));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn limited(self, limit: usize) -> LimitedBodyReader<B> {
+        LimitedBodyReader {
+            body: self.0,
+            limit,
+        }
+    }
+
+    /// Turns the body into a stream of data chunks.
+    ///
+    /// The chunks are yielded as soon as their frames arrive, so the body can be processed
+    /// without buffering it entirely, for example, written to a file. The trailers frames
+    /// are skipped. A read error is yielded as the last stream item.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_util::StreamExt as _;
+    /// use http_body_util::Full;
+    /// use tower_http_client::client::BodyReader;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let body = Full::new("Hello world".as_bytes());
+    ///     let mut chunks = std::pin::pin!(BodyReader::new(body).into_stream());
+    ///
+    ///     while let Some(chunk) = chunks.next().await {
+    ///         println!("received {} bytes", chunk?.len());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn into_stream(self) -> impl Stream<Item = Result<Bytes, B::Error>>
+    where
+        B: Body,
+        B::Data: Buf,
+    {
+        futures_util::stream::unfold(Some(Box::pin(self.0)), |body| async move {
+            let mut body = body?;
+            loop {
+                match body.frame().await? {
+                    Ok(frame) => {
+                        if let Ok(mut data) = frame.into_data() {
+                            if data.has_remaining() {
+                                let chunk = data.copy_to_bytes(data.remaining());
+                                return Some((Ok(chunk), Some(body)));
+                            }
+                        }
+                    }
+                    // Don't poll the body after an error.
+                    Err(err) => return Some((Err(err), None)),
+                }
+            }
+        })
+    }
+
     /// Reads the full response body as [`Bytes`].
     ///
     /// # Example
@@ -51,8 +160,95 @@ impl<B> BodyReader<B> {
         B: Body,
         B::Data: Buf,
     {
-        let body_bytes = self.0.collect().await?.to_bytes();
-        Ok(body_bytes)
+        let bytes = collect_chunks(self.into_stream(), usize::MAX).await?;
+        Ok(bytes.unwrap_or_default())
+    }
+
+    /// Reads the full response body and verifies its checksum.
+    ///
+    /// The checksums are taken from the given response headers:
+    ///
+    /// - the `Content-MD5` header with the base64-encoded MD5 digest;
+    /// - the `Digest` header with the `md5` or `sha-256` digests, like `sha-256=X48E9q...`;
+    /// - the `Content-Digest` header with the same digests in the structured form,
+    ///   like `sha-256=:X48E9q...:`.
+    ///
+    /// The digests are computed while the body is read. Each advertised checksum of
+    /// a supported algorithm is verified, the other ones are ignored. If there are no
+    /// supported checksums, the body is returned without verification.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use http::HeaderMap;
+    /// use http_body_util::Full;
+    /// use tower_http_client::client::BodyReader;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let mut headers = HeaderMap::new();
+    ///     headers.insert("content-md5", "PiWWCnnbxptnTNTsZ6csYg==".parse()?);
+    ///
+    ///     let body = Full::new("Hello world".as_bytes());
+    ///     let content = BodyReader::new(body).bytes_verified(&headers).await?;
+    ///
+    ///     assert_eq!(content, "Hello world");
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "digest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+    pub async fn bytes_verified(
+        self,
+        headers: &http::HeaderMap,
+    ) -> Result<Bytes, BodyReaderError<B::Error, ChecksumMismatch>>
+    where
+        B: Body,
+        B::Data: Buf,
+    {
+        use base64::Engine as _;
+        use sha2::Digest as _;
+
+        let expected = advertised_checksums(headers);
+        let mut md5 = expected
+            .iter()
+            .any(|(algorithm, _)| *algorithm == MD5)
+            .then(md5::Md5::new);
+        let mut sha256 = expected
+            .iter()
+            .any(|(algorithm, _)| *algorithm == SHA_256)
+            .then(sha2::Sha256::new);
+
+        let chunks = self.into_stream().inspect(|chunk| {
+            if let Ok(chunk) = chunk {
+                if let Some(md5) = &mut md5 {
+                    md5.update(chunk);
+                }
+                if let Some(sha256) = &mut sha256 {
+                    sha256.update(chunk);
+                }
+            }
+        });
+        let bytes = collect_chunks(chunks, usize::MAX)
+            .await
+            .map_err(BodyReaderError::Read)?
+            .unwrap_or_default();
+
+        let md5 = md5.map(|md5| md5.finalize().to_vec());
+        let sha256 = sha256.map(|sha256| sha256.finalize().to_vec());
+        for (algorithm, checksum) in expected {
+            let actual = if algorithm == MD5 { &md5 } else { &sha256 };
+            let actual = actual.as_deref().unwrap_or_default();
+            let engine = base64::engine::general_purpose::STANDARD;
+            if engine.decode(checksum).ok().as_deref() != Some(actual) {
+                return Err(BodyReaderError::Decode(ChecksumMismatch {
+                    algorithm,
+                    expected: checksum.to_owned(),
+                    actual: engine.encode(actual),
+                }));
+            }
+        }
+        Ok(bytes)
     }
 
     /// Reads the full response text.
@@ -86,6 +282,117 @@ impl<B> BodyReader<B> {
         String::from_utf8(bytes.into()).map_err(BodyReaderError::Decode)
     }
 
+    /// Reads the full response text using the charset from the given `Content-Type` value.
+    ///
+    /// The text is decoded according to the `charset` parameter of the content type, or as
+    /// `UTF-8` if there is no such parameter. Invalid byte sequences are replaced with
+    /// the replacement character, just like browsers do.
+    ///
+    /// Prefer the [`ResponseExt::text`] method which reads the content type from the
+    /// response headers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use http::HeaderValue;
+    /// use http_body_util::Full;
+    /// use tower_http_client::client::BodyReader;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let content_type = HeaderValue::from_static("text/plain; charset=windows-1252");
+    ///     let body = Full::new(&b"Caf\xe9"[..]);
+    ///     let content = BodyReader::new(body).text(Some(&content_type)).await?;
+    ///
+    ///     assert_eq!(content, "Café");
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`ResponseExt::text`]: crate::client::ResponseExt::text
+    #[cfg(feature = "charset")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "charset")))]
+    pub async fn text(
+        self,
+        content_type: Option<&http::HeaderValue>,
+    ) -> Result<String, BodyReaderError<B::Error, UnknownCharsetError>>
+    where
+        B: Body,
+        B::Data: Buf,
+    {
+        let label = content_type
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| crate::mime::parameter(value, "charset"));
+        let encoding = match label {
+            Some(label) => encoding_rs::Encoding::for_label(label.as_bytes()).ok_or_else(|| {
+                BodyReaderError::Decode(UnknownCharsetError {
+                    label: label.to_owned(),
+                })
+            })?,
+            None => encoding_rs::UTF_8,
+        };
+
+        let bytes = self.bytes().await.map_err(BodyReaderError::Read)?;
+        let (text, _, _) = encoding.decode(&bytes);
+        Ok(text.into_owned())
+    }
+
+    /// Reads the `multipart/byteranges` response to the request with several ranges.
+    ///
+    /// The boundary is taken from the given `Content-Type` header value, and the parts are
+    /// validated against the `requested` inclusive byte ranges, see the [`parse_byteranges`]
+    /// function for details. The ranges are returned in the order they appear in the body.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use http::HeaderValue;
+    /// use http_body_util::Full;
+    /// use tower_http_client::client::BodyReader;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let content_type = HeaderValue::from_static("multipart/byteranges; boundary=SEP");
+    ///     let body = Full::new(
+    ///         "--SEP\r\nContent-Range: bytes 0-4/20\r\n\r\nHello\r\n--SEP--\r\n".as_bytes(),
+    ///     );
+    ///     let ranges = BodyReader::new(body)
+    ///         .byte_ranges(Some(&content_type), &[(0, 4)])
+    ///         .await?;
+    ///
+    ///     assert_eq!(ranges[0].0.start, 0);
+    ///     assert_eq!(ranges[0].1, "Hello");
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`parse_byteranges`]: super::multipart::parse_byteranges
+    #[cfg(feature = "multipart")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "multipart")))]
+    pub async fn byte_ranges(
+        self,
+        content_type: Option<&http::HeaderValue>,
+        requested: &[(u64, u64)],
+    ) -> Result<
+        Vec<(super::multipart::ContentRange, Bytes)>,
+        BodyReaderError<B::Error, super::multipart::ByteRangesError>,
+    >
+    where
+        B: Body,
+        B::Data: Buf,
+    {
+        use super::multipart::{parse_byteranges, ByteRangesError, MultipartParseError};
+
+        let boundary = content_type
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| crate::mime::parameter(value, "boundary"))
+            .ok_or(BodyReaderError::Decode(ByteRangesError::Multipart(
+                MultipartParseError::MissingBoundary,
+            )))?;
+        let bytes = self.bytes().await.map_err(BodyReaderError::Read)?;
+        parse_byteranges(boundary, &bytes, requested).map_err(BodyReaderError::Decode)
+    }
+
     /// Deserializes the response body as JSON.
     ///
     /// # Examples
@@ -116,4 +423,580 @@ impl<B> BodyReader<B> {
         let bytes = self.bytes().await.map_err(BodyReaderError::Read)?;
         serde_json::from_slice(&bytes).map_err(BodyReaderError::Decode)
     }
+
+    /// Deserializes the response body as JSON rejecting the unknown top-level fields.
+    ///
+    /// It works like the `#[serde(deny_unknown_fields)]` attribute, but without changing
+    /// the type, so the same type can be read leniently in production and strictly in tests
+    /// to catch unexpected API additions. If the body contains a field, which is not
+    /// declared by the top-level struct, the same error as for the attribute is returned.
+    ///
+    /// Only the top-level struct fields are checked, so the types with the flattened fields
+    /// or the non-struct types are read as by the [`json`](Self::json) method.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use http_body_util::Full;
+    /// use tower_http_client::client::BodyReader;
+    ///
+    /// #[derive(Debug, serde::Deserialize)]
+    /// struct User {
+    ///     id: u32,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let body = Full::new("{\"id\":1,\"name\":\"Alice\"}".as_bytes());
+    ///     let error = BodyReader::new(body).json_strict::<User>().await.unwrap_err();
+    ///
+    ///     assert!(error.to_string().contains("unknown field `name`"));
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn json_strict<T>(self) -> Result<T, BodyReaderError<B::Error, serde_json::Error>>
+    where
+        T: serde::de::DeserializeOwned,
+        B: Body,
+        B::Data: Buf,
+    {
+        let bytes = self.bytes().await.map_err(BodyReaderError::Read)?;
+        let value: serde_json::Value =
+            serde_json::from_slice(&bytes).map_err(BodyReaderError::Decode)?;
+        let keys = value
+            .as_object()
+            .map(|object| object.keys().cloned().collect::<Vec<_>>());
+
+        let mut fields = None;
+        let content = T::deserialize(StructFieldsRecorder {
+            value,
+            fields: &mut fields,
+        })
+        .map_err(BodyReaderError::Decode)?;
+        if let (Some(keys), Some(fields)) = (keys, fields) {
+            if let Some(key) = keys.iter().find(|key| !fields.contains(&key.as_str())) {
+                return Err(BodyReaderError::Decode(serde::de::Error::unknown_field(
+                    key, fields,
+                )));
+            }
+        }
+        Ok(content)
+    }
+
+    /// Deserializes the response body as a stream of newline-delimited JSON values.
+    ///
+    /// Unlike the [`json`](Self::json) method, the body is not buffered entirely: the values
+    /// are yielded as soon as their lines arrive, even if a line is split across several
+    /// body frames. Blank lines are skipped, and the trailing data without the terminating
+    /// newline is parsed at the end of the body.
+    ///
+    /// A read error is yielded as the last stream item, while a decode error only affects
+    /// its own line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt as _;
+    /// use http_body_util::Full;
+    /// use serde_json::Value;
+    /// use tower_http_client::client::BodyReader;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let body = Full::new("{\"id\":1}\n{\"id\":2}\n".as_bytes());
+    ///     let mut values = std::pin::pin!(BodyReader::new(body).json_lines::<Value>());
+    ///
+    ///     while let Some(value) = values.next().await {
+    ///         println!("{}", value?["id"]);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn json_lines<T>(
+        self,
+    ) -> impl futures_util::Stream<Item = Result<T, BodyReaderError<B::Error, serde_json::Error>>>
+    where
+        T: serde::de::DeserializeOwned,
+        B: Body,
+        B::Data: Buf,
+    {
+        let state = (Box::pin(self.into_stream()), BytesMut::new(), false);
+        futures_util::stream::unfold(state, |(mut chunks, mut buffer, mut finished)| async move {
+            loop {
+                let line = match buffer.iter().position(|&byte| byte == b'\n') {
+                    Some(position) => buffer.split_to(position + 1),
+                    // Flush the trailing data without the newline.
+                    None if finished => buffer.split(),
+                    None => {
+                        match chunks.next().await {
+                            Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+                            Some(Err(err)) => {
+                                buffer.clear();
+                                return Some((
+                                    Err(BodyReaderError::Read(err)),
+                                    (chunks, buffer, true),
+                                ));
+                            }
+                            None => finished = true,
+                        }
+                        continue;
+                    }
+                };
+
+                if line.is_empty() {
+                    return None;
+                }
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                let value = serde_json::from_slice(&line).map_err(BodyReaderError::Decode);
+                return Some((value, (chunks, buffer, finished)));
+            }
+        })
+    }
+
+    /// Deserializes the response body as [JSON5].
+    ///
+    /// Unlike the strict `json` method, this one tolerates comments, trailing
+    /// commas and other JSON5 extensions, which is useful for the endpoints that emit
+    /// config-like output.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use http_body_util::Full;
+    /// use serde::Deserialize;
+    /// use tower_http_client::client::BodyReader;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Data {
+    ///     id: u32,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let body = Full::new("{ id: 1234, /* comment */ }".as_bytes());
+    ///     let content: Data = BodyReader::new(body).json_lenient().await?;
+    ///
+    ///     assert_eq!(content.id, 1234);
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [JSON5]: https://json5.org
+    #[cfg(feature = "json5")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json5")))]
+    pub async fn json_lenient<T>(self) -> Result<T, BodyReaderError<B::Error, json5::Error>>
+    where
+        T: serde::de::DeserializeOwned,
+        B: Body,
+        B::Data: Buf,
+    {
+        use serde::de::Error as _;
+
+        let bytes = self.bytes().await.map_err(BodyReaderError::Read)?;
+        let text = std::str::from_utf8(&bytes)
+            .map_err(|err| BodyReaderError::Decode(json5::Error::custom(err)))?;
+        json5::from_str(text).map_err(BodyReaderError::Decode)
+    }
+}
+
+/// Body reader that limits the number of bytes that can be read from the body.
+///
+/// This reader is created by the [`BodyReader::limited`] method.
+#[derive(Debug, Clone)]
+pub struct LimitedBodyReader<B> {
+    body: B,
+    limit: usize,
+}
+
+impl<B> LimitedBodyReader<B> {
+    /// Reads the full response body as [`Bytes`].
+    ///
+    /// Since there is nothing to decode, the error is never [`BodyReaderError::Decode`].
+    pub async fn bytes(self) -> Result<Bytes, BodyReaderError<B::Error, Infallible>>
+    where
+        B: Body,
+        B::Data: Buf,
+    {
+        self.collect().await
+    }
+
+    /// Reads the full response text.
+    ///
+    /// See the [`BodyReader::utf8`] method for more details.
+    pub async fn utf8(self) -> Result<String, BodyReaderError<B::Error, FromUtf8Error>>
+    where
+        B: Body,
+        B::Data: Buf,
+    {
+        let bytes = self.collect().await?;
+        String::from_utf8(bytes.into()).map_err(BodyReaderError::Decode)
+    }
+
+    /// Deserializes the response body as JSON.
+    ///
+    /// See the [`BodyReader::json`] method for more details.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn json<T>(self) -> Result<T, BodyReaderError<B::Error, serde_json::Error>>
+    where
+        T: serde::de::DeserializeOwned,
+        B: Body,
+        B::Data: Buf,
+    {
+        let bytes = self.collect().await?;
+        serde_json::from_slice(&bytes).map_err(BodyReaderError::Decode)
+    }
+
+    async fn collect<D>(self) -> Result<Bytes, BodyReaderError<B::Error, D>>
+    where
+        B: Body,
+        B::Data: Buf,
+    {
+        let limit = self.limit;
+        let too_large = || BodyReaderError::TooLarge { limit };
+        if self.body.size_hint().lower() > limit as u64 {
+            return Err(too_large());
+        }
+
+        let chunks = BodyReader::new(self.body).into_stream();
+        collect_chunks(chunks, limit)
+            .await
+            .map_err(BodyReaderError::Read)?
+            .ok_or_else(too_large)
+    }
+}
+
+// Concatenates the data chunks, returns `None` as soon as the data exceeds the limit.
+//
+// A single chunk is returned as is without copying.
+async fn collect_chunks<E>(
+    chunks: impl Stream<Item = Result<Bytes, E>>,
+    limit: usize,
+) -> Result<Option<Bytes>, E> {
+    let mut chunks = std::pin::pin!(chunks);
+    let mut first = None;
+    let mut bytes = BytesMut::new();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk?;
+        let len = first.as_ref().map_or(bytes.len(), Bytes::len);
+        if len.saturating_add(chunk.len()) > limit {
+            return Ok(None);
+        }
+        if bytes.is_empty() {
+            match first.take() {
+                None => {
+                    first = Some(chunk);
+                    continue;
+                }
+                Some(first) => bytes.extend_from_slice(&first),
+            }
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(Some(first.unwrap_or_else(|| bytes.freeze())))
+}
+
+#[cfg(feature = "digest")]
+const MD5: &str = "md5";
+#[cfg(feature = "digest")]
+const SHA_256: &str = "sha-256";
+
+// Collects the base64-encoded checksums of the supported algorithms.
+#[cfg(feature = "digest")]
+fn advertised_checksums(headers: &http::HeaderMap) -> Vec<(&'static str, &str)> {
+    let mut checksums = headers
+        .get_all("content-md5")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .map(|value| (MD5, value.trim()))
+        .collect::<Vec<_>>();
+
+    let digests = headers
+        .get_all("digest")
+        .iter()
+        .chain(headers.get_all("content-digest"))
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','));
+    for digest in digests {
+        let Some((algorithm, value)) = digest.split_once('=') else {
+            continue;
+        };
+        let algorithm = match algorithm.trim() {
+            algorithm if algorithm.eq_ignore_ascii_case(MD5) => MD5,
+            algorithm if algorithm.eq_ignore_ascii_case(SHA_256) => SHA_256,
+            _ => continue,
+        };
+        // The structured field form wraps the byte sequence into colons.
+        let value = value.trim();
+        let value = value
+            .strip_prefix(':')
+            .and_then(|value| value.strip_suffix(':'))
+            .unwrap_or(value);
+        checksums.push((algorithm, value));
+    }
+    checksums
+}
+
+// Deserializer, which records the fields of the deserialized struct.
+#[cfg(feature = "json")]
+struct StructFieldsRecorder<'a> {
+    value: serde_json::Value,
+    fields: &'a mut Option<&'static [&'static str]>,
+}
+
+#[cfg(feature = "json")]
+macro_rules! forward_to_value {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V: serde::de::Visitor<'de>>(
+                self,
+                $($arg: $ty,)*
+                visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                self.value.$method($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "json")]
+impl<'de> serde::Deserializer<'de> for StructFieldsRecorder<'_> {
+    type Error = serde_json::Error;
+
+    forward_to_value! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+
+    fn deserialize_struct<V: serde::de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.fields = Some(fields);
+        self.value.deserialize_struct(name, fields, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures_util::stream;
+    use http_body::Frame;
+    use http_body_util::StreamBody;
+
+    use super::{BodyReader, BodyReaderError};
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn test_body_reader_json_strict() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct User {
+            id: u32,
+            #[serde(rename = "displayName")]
+            display_name: String,
+            tags: Vec<serde_json::Value>,
+        }
+
+        let read = |body: &'static str| {
+            BodyReader::new(http_body_util::Full::new(body.as_bytes())).json_strict::<User>()
+        };
+
+        // Only the top-level fields are checked.
+        let user = read(r#"{"id":1,"displayName":"Alice","tags":[{"extra":true}]}"#)
+            .await
+            .unwrap();
+        assert_eq!(user.display_name, "Alice");
+
+        let error = read(r#"{"id":1,"displayName":"Alice","tags":[],"email":"a@b.c"}"#)
+            .await
+            .unwrap_err();
+        assert!(matches!(&error, BodyReaderError::Decode(_)));
+        assert!(error.to_string().starts_with("unknown field `email`"));
+
+        // Non-struct types are read as is.
+        let values = BodyReader::new(http_body_util::Full::new(&b"[1,2]"[..]))
+            .json_strict::<Vec<u32>>()
+            .await
+            .unwrap();
+        assert_eq!(values, [1, 2]);
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn test_body_reader_json_lines() {
+        use futures_util::StreamExt as _;
+
+        let frames = [
+            Ok::<_, &'static str>(Frame::data(Bytes::from_static(b"{\"id\":1}\n{\"i"))),
+            Ok(Frame::data(Bytes::from_static(b"d\":2}\r\n\n"))),
+            Ok(Frame::data(Bytes::from_static(b"oops\n{\"id\":3}"))),
+        ];
+        let body = StreamBody::new(stream::iter(frames));
+        let values = BodyReader::new(body)
+            .json_lines::<serde_json::Value>()
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(values.len(), 4);
+        assert_eq!(values[0].as_ref().unwrap()["id"], 1);
+        assert_eq!(values[1].as_ref().unwrap()["id"], 2);
+        assert!(matches!(values[2], Err(BodyReaderError::Decode(_))));
+        assert_eq!(values[3].as_ref().unwrap()["id"], 3);
+
+        // The read error ends the stream.
+        let frames = [
+            Ok(Frame::data(Bytes::from_static(b"{\"id\":1}\n{\"id\""))),
+            Err("connection reset"),
+        ];
+        let body = StreamBody::new(stream::iter(frames));
+        let values = BodyReader::new(body)
+            .json_lines::<serde_json::Value>()
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0].as_ref().unwrap()["id"], 1);
+        assert!(matches!(
+            values[1],
+            Err(BodyReaderError::Read("connection reset"))
+        ));
+    }
+
+    #[cfg(feature = "digest")]
+    #[tokio::test]
+    async fn test_body_reader_bytes_verified() {
+        use super::ChecksumMismatch;
+
+        const MD5: &str = "PiWWCnnbxptnTNTsZ6csYg==";
+        const SHA_256: &str = "ZOyIygCyaOW6GjVnihtTFtIS9PNmskdyMlNKiuyjfzw=";
+
+        async fn read(
+            headers: &[(&'static str, String)],
+        ) -> Result<Bytes, BodyReaderError<&'static str, ChecksumMismatch>> {
+            let mut map = http::HeaderMap::new();
+            for (name, value) in headers {
+                map.append(*name, value.parse().unwrap());
+            }
+            let frames = [
+                Ok::<_, &'static str>(Frame::data(Bytes::from_static(b"Hello "))),
+                Ok(Frame::data(Bytes::from_static(b"world"))),
+            ];
+            BodyReader::new(StreamBody::new(stream::iter(frames)))
+                .bytes_verified(&map)
+                .await
+        }
+
+        let valid = [
+            vec![("content-md5", MD5.to_owned())],
+            vec![("digest", format!("SHA-256={SHA_256}, unixsum=30637"))],
+            vec![(
+                "content-digest",
+                format!("sha-256=:{SHA_256}:, md5=:{MD5}:"),
+            )],
+            vec![],
+        ];
+        for headers in valid {
+            assert_eq!(read(&headers).await.unwrap(), "Hello world");
+        }
+
+        let headers = [
+            ("content-md5", MD5.to_owned()),
+            ("digest", "sha-256=AAAA".to_owned()),
+        ];
+        let error = read(&headers).await.unwrap_err();
+        assert!(matches!(
+            error,
+            BodyReaderError::Decode(ChecksumMismatch {
+                algorithm: "sha-256",
+                expected,
+                actual,
+            }) if expected == "AAAA" && actual == SHA_256
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_body_reader_into_stream() {
+        use futures_util::StreamExt as _;
+
+        let mut trailers = http::HeaderMap::new();
+        trailers.insert("x-checksum", http::HeaderValue::from_static("abc"));
+        let frames = [
+            Ok(Frame::data(Bytes::from_static(b"Hel"))),
+            Ok(Frame::data(Bytes::new())),
+            Ok(Frame::data(Bytes::from_static(b"lo"))),
+            Ok(Frame::trailers(trailers)),
+            Err("connection reset"),
+            Ok(Frame::data(Bytes::from_static(b"!"))),
+        ];
+        let body = StreamBody::new(stream::iter(frames));
+        let chunks = BodyReader::new(body)
+            .into_stream()
+            .collect::<Vec<_>>()
+            .await;
+
+        // Empty chunks and trailers are skipped, and the error ends the stream.
+        assert_eq!(
+            chunks,
+            [
+                Ok(Bytes::from_static(b"Hel")),
+                Ok(Bytes::from_static(b"lo")),
+                Err("connection reset"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_body_reader_limited_stream() {
+        let chunks = || {
+            stream::iter([
+                Ok::<_, std::convert::Infallible>(Frame::data(Bytes::from_static(b"Hel"))),
+                Ok(Frame::data(Bytes::from_static(b"lo"))),
+                Ok(Frame::data(Bytes::from_static(b"!"))),
+            ])
+        };
+
+        let body = StreamBody::new(chunks());
+        let text = BodyReader::new(body).limited(6).utf8().await.unwrap();
+        assert_eq!(text, "Hello!");
+
+        let body = StreamBody::new(chunks());
+        let error = BodyReader::new(body).limited(5).bytes().await.unwrap_err();
+        assert!(matches!(error, BodyReaderError::TooLarge { limit: 5 }));
+    }
 }
@@ -0,0 +1,369 @@
+//! Decompression of response bodies with the limited decompressed size.
+//!
+//! The `Content-Length` header of a compressed response reflects the compressed size only,
+//! so a small response can be decompressed into gigabytes of data, which is known as
+//! the decompression bomb. The content is decoded by the [`flate2`] decoders chunk by chunk,
+//! and the decoding stops with the [`DecompressError::DecompressedTooLarge`] error before
+//! the output exceeds the limit.
+//!
+//! The `gzip` (`x-gzip`), `deflate` and `identity` content codings are supported.
+//!
+//! [`flate2`]: https://docs.rs/flate2
+
+use std::io::{self, Write};
+
+use bytes::Bytes;
+use flate2::{write::MultiGzDecoder, Decompress, FlushDecompress, Status};
+use thiserror::Error;
+
+// The size of the buffer for the inflated data.
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// Errors that can occur while decompressing the content.
+#[derive(Debug, Error)]
+pub enum DecompressError {
+    /// The content coding is not supported.
+    #[error("unsupported content encoding: {encoding}")]
+    UnsupportedEncoding {
+        /// The content coding name.
+        encoding: String,
+    },
+    /// The compressed data is corrupted.
+    #[error("malformed compressed data")]
+    Malformed,
+    /// The decompressed content is larger than the limit.
+    #[error("decompressed content is larger than the limit of {limit} bytes")]
+    DecompressedTooLarge {
+        /// The maximum allowed decompressed length in bytes.
+        limit: usize,
+    },
+}
+
+/// Extension that disables the decompression of the response body.
+///
+/// The [`ResponseExt::decompressed`] method returns the body of the response with this
+/// extension as is, so a client can pass the compressed bodies through for the proxy
+/// routes and still decompress them for the others. If the extension is inserted into
+/// the request, the [`ServiceExt::execute`] method copies it into the response.
+///
+/// # Example
+///
+/// ```
+/// use bytes::Bytes;
+/// use http_body_util::Full;
+/// use tower_http_client::{client::decompress::NoDecompress, ResponseExt as _};
+///
+/// #[tokio::main]
+/// async fn main() -> anyhow::Result<()> {
+///     let response = http::Response::builder()
+///         .header("content-encoding", "gzip")
+///         .extension(NoDecompress)
+///         .body(Full::new(Bytes::from_static(b"compressed")))?;
+///
+///     let body = response.decompressed(1024).await?;
+///     assert_eq!(body, "compressed");
+///     Ok(())
+/// }
+/// ```
+///
+/// [`ResponseExt::decompressed`]: crate::ResponseExt::decompressed
+/// [`ServiceExt::execute`]: crate::ServiceExt::execute
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoDecompress;
+
+/// Decompresses the content according to the `Content-Encoding` header value.
+///
+/// The header value lists the codings in the order they were applied, so they are
+/// decoded in the reverse order. The decompressed content must not exceed the `limit` bytes.
+///
+/// # Errors
+///
+/// If a coding is not supported, the data is corrupted or the decompressed content is
+/// larger than the limit.
+pub fn decompress(encoding: &str, content: &[u8], limit: usize) -> Result<Bytes, DecompressError> {
+    let mut decoder = Decoder::new(encoding, limit)?;
+    decoder.write(content)?;
+    decoder.finish()
+}
+
+// Decoder, which is fed with the content chunks as they are received.
+pub(crate) struct Decoder(Box<dyn Stage>);
+
+impl Decoder {
+    pub(crate) fn new(encoding: &str, limit: usize) -> Result<Self, DecompressError> {
+        let mut stage: Box<dyn Stage> = Box::new(LimitedOutput {
+            output: Vec::new(),
+            limit,
+        });
+        // The first applied coding is decoded last, so it is the closest to the output.
+        for coding in encoding.split(',').map(str::trim) {
+            stage = match coding.to_ascii_lowercase().as_str() {
+                "" | "identity" => stage,
+                "gzip" | "x-gzip" => Box::new(MultiGzDecoder::new(stage)),
+                "deflate" => Box::new(Inflate::new(stage)),
+                _ => {
+                    return Err(DecompressError::UnsupportedEncoding {
+                        encoding: coding.to_owned(),
+                    })
+                }
+            };
+        }
+        Ok(Self(stage))
+    }
+
+    pub(crate) fn write(&mut self, chunk: &[u8]) -> Result<(), DecompressError> {
+        self.0.write_all(chunk).map_err(decompress_error)
+    }
+
+    pub(crate) fn finish(self) -> Result<Bytes, DecompressError> {
+        self.0.finish().map(Bytes::from).map_err(decompress_error)
+    }
+}
+
+// Extracts the decompression error, which is passed through the decoders as `io::Error`.
+fn decompress_error(error: io::Error) -> DecompressError {
+    error
+        .into_inner()
+        .and_then(|error| error.downcast::<DecompressError>().ok())
+        .map_or(DecompressError::Malformed, |error| *error)
+}
+
+// Stage of the decoding chain, which writes the decoded data into the next stage.
+trait Stage: Write + Send {
+    // Checks that the data is complete and returns the decompressed content.
+    fn finish(self: Box<Self>) -> io::Result<Vec<u8>>;
+}
+
+// The last stage, which collects the decompressed content up to the limit.
+struct LimitedOutput {
+    output: Vec<u8>,
+    limit: usize,
+}
+
+impl Write for LimitedOutput {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.output.len().saturating_add(data.len()) > self.limit {
+            return Err(io::Error::other(DecompressError::DecompressedTooLarge {
+                limit: self.limit,
+            }));
+        }
+        self.output.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Stage for LimitedOutput {
+    fn finish(self: Box<Self>) -> io::Result<Vec<u8>> {
+        Ok(self.output)
+    }
+}
+
+// Decodes the gzip members defined in RFC 1952.
+impl Stage for MultiGzDecoder<Box<dyn Stage>> {
+    fn finish(self: Box<Self>) -> io::Result<Vec<u8>> {
+        MultiGzDecoder::finish(*self)?.finish()
+    }
+}
+
+// Decodes the zlib stream defined in RFC 1950, falling back to the raw deflate stream,
+// which is sent by some servers despite the specification.
+struct Inflate {
+    next: Box<dyn Stage>,
+    // The first bytes, which are buffered until the stream format is detected.
+    header: Vec<u8>,
+    state: Option<Decompress>,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl Inflate {
+    fn new(next: Box<dyn Stage>) -> Self {
+        Self {
+            next,
+            header: Vec::new(),
+            state: None,
+            buffer: Vec::with_capacity(BUFFER_SIZE),
+            done: false,
+        }
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        let is_zlib = matches!(
+            *self.header,
+            [cmf, flags, ..] if cmf & 0x0f == 8 && (u16::from(cmf) << 8 | u16::from(flags)) % 31 == 0
+        );
+        self.state = Some(Decompress::new(is_zlib));
+        let header = std::mem::take(&mut self.header);
+        self.inflate(&header, FlushDecompress::None)
+    }
+
+    fn inflate(&mut self, mut input: &[u8], flush: FlushDecompress) -> io::Result<()> {
+        let state = self
+            .state
+            .as_mut()
+            .expect("the stream format must be detected");
+        // The data after the end of the stream is ignored.
+        while !self.done {
+            let total_in = state.total_in();
+            self.buffer.clear();
+            let status = state
+                .decompress_vec(input, &mut self.buffer, flush)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            // The consumed length never exceeds the input length.
+            #[allow(clippy::cast_possible_truncation)]
+            let consumed = (state.total_in() - total_in) as usize;
+            input = &input[consumed..];
+            self.next.write_all(&self.buffer)?;
+
+            let is_full = self.buffer.len() == self.buffer.capacity();
+            match status {
+                Status::StreamEnd => self.done = true,
+                _ if consumed == 0 && self.buffer.is_empty() => break,
+                _ if input.is_empty() && !is_full => break,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Write for Inflate {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.state.is_some() {
+            self.inflate(data, FlushDecompress::None)?;
+        } else {
+            self.header.extend_from_slice(data);
+            if self.header.len() >= 2 {
+                self.start()?;
+            }
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.next.flush()
+    }
+}
+
+impl Stage for Inflate {
+    fn finish(mut self: Box<Self>) -> io::Result<Vec<u8>> {
+        if self.state.is_none() {
+            self.start()?;
+        }
+        self.inflate(&[], FlushDecompress::Finish)?;
+        if !self.done {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.next.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use http_body_util::Full;
+    use pretty_assertions::assert_eq;
+
+    use super::{decompress, Decoder, DecompressError, NoDecompress};
+    use crate::{client::body_reader::BodyReaderError, ResponseExt as _};
+
+    // "Hello, world!" compressed with the fixed Huffman codes.
+    const GZIP_FIXED: &[u8] = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\xf3\x48\xcd\xc9\xc9\xd7\
+        \x51\x28\xcf\x2f\xca\x49\x51\x04\x00\xe6\xc6\xe6\xeb\x0d\x00\x00\x00";
+    // "Hello, world!" stored without compression.
+    const ZLIB_STORED: &[u8] = b"\x78\x01\x01\x0d\x00\xf2\xff\x48\x65\x6c\x6c\x6f\x2c\x20\x77\
+        \x6f\x72\x6c\x64\x21\x20\x5e\x04\x8a";
+    // The `users_json` content compressed with the dynamic Huffman codes.
+    const ZLIB_DYNAMIC: &[u8] = b"\x78\xda\x75\xd1\x3b\x0a\x80\x40\x0c\x04\xd0\xbb\xa4\xde\x62\
+        \xa3\xae\xbf\xab\x88\xc5\xa2\x2b\x08\x6a\xe1\xaf\x11\xef\xae\x20\x88\x83\x4c\x1b\x1e\x33\
+        \x21\xa9\x0e\xe9\x5b\x29\xad\x91\xc9\x8f\x41\x4a\xd9\x96\x30\x5b\x31\xe2\x9b\xb5\xdf\xef\
+        \x41\xe7\x87\x25\x9c\xe6\x61\x0a\x2c\xfb\xb0\x75\xde\x5e\x15\x81\x52\xa2\x62\x50\x39\xab\
+        \x4c\x80\x45\x24\xcc\x81\x2a\x88\x4a\x41\xc5\xac\x32\xc3\xfd\x2d\x49\xcb\x81\x25\x44\x15\
+        \x18\xa6\xf4\xb4\xf8\x02\x47\xe2\x14\x5f\xa0\xbf\x83\xd4\x17\xe5\xe0\x93\x2a";
+
+    fn users_json() -> String {
+        let users = (0..12)
+            .map(|id| {
+                format!(
+                    r#"{{"id":{id},"name":"user{}","active":{}}}"#,
+                    id * 7 % 13,
+                    id % 3 != 0
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("[{}]", users.join(","))
+    }
+
+    #[test]
+    fn test_decompress() -> anyhow::Result<()> {
+        let hello = Bytes::from_static(b"Hello, world!");
+        assert_eq!(decompress("gzip", GZIP_FIXED, 13)?, hello);
+        assert_eq!(decompress("deflate", ZLIB_STORED, 13)?, hello);
+        assert_eq!(decompress("deflate", ZLIB_DYNAMIC, 1024)?, users_json());
+        // Raw deflate stream without the zlib wrapper.
+        assert_eq!(decompress("deflate", &GZIP_FIXED[10..], 13)?, hello);
+        assert_eq!(decompress("identity", &hello, 13)?, hello);
+        // The content is decoded chunk by chunk.
+        let mut decoder = Decoder::new("identity, deflate", 1024)?;
+        for byte in ZLIB_DYNAMIC {
+            decoder.write(std::slice::from_ref(byte))?;
+        }
+        assert_eq!(decoder.finish()?, users_json());
+
+        assert!(matches!(
+            decompress("gzip", GZIP_FIXED, 12),
+            Err(DecompressError::DecompressedTooLarge { limit: 12 })
+        ));
+        assert!(matches!(
+            decompress("gzip", &GZIP_FIXED[..20], 13),
+            Err(DecompressError::Malformed)
+        ));
+        assert!(matches!(
+            decompress("deflate", &ZLIB_DYNAMIC[..40], 1024),
+            Err(DecompressError::Malformed)
+        ));
+        assert!(matches!(
+            decompress("br", &hello, 13),
+            Err(DecompressError::UnsupportedEncoding { encoding }) if encoding == "br"
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_response_ext_decompressed() -> anyhow::Result<()> {
+        // 100 KB of zeros compressed into 120 bytes.
+        let mut bomb =
+            b"\x78\xda\xed\xc1\x31\x01\x00\x00\x00\xc2\xa0\xf5\x4f\x6d\x0d\x0f\xa0".to_vec();
+        bomb.resize(bomb.len() + 96, 0);
+        bomb.extend_from_slice(b"\x80\x57\x03\x86\xaf\x00\x01");
+
+        let response = |body: Vec<u8>| {
+            http::Response::builder()
+                .header("content-encoding", "deflate")
+                .body(Full::new(Bytes::from(body)))
+                .unwrap()
+        };
+
+        let bytes = response(bomb.clone()).decompressed(100_000).await?;
+        assert_eq!(bytes.len(), 100_000);
+
+        let error = response(bomb.clone())
+            .decompressed(64 * 1024)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            BodyReaderError::Decode(DecompressError::DecompressedTooLarge { limit: 65536 })
+        ));
+
+        // The body is passed through as is.
+        let mut passthrough = response(bomb.clone());
+        passthrough.extensions_mut().insert(NoDecompress);
+        assert_eq!(passthrough.decompressed(1024).await?, bomb);
+        Ok(())
+    }
+}
@@ -1,13 +1,39 @@
 //! Extensions for Tower services that provide HTTP clients implementation.
 
+#[cfg(feature = "decompression")]
+pub use self::decompress::NoDecompress;
+#[cfg(feature = "rate-limit")]
+pub use self::rate_limit::{RateLimit, RateLimitHeaders};
+#[cfg(all(feature = "json", feature = "xml"))]
+pub use self::service_ext::NegotiateError;
 pub use self::{
-    body_reader::BodyReader, into_uri::IntoUri, request_builder::ClientRequest,
-    response_ext::ResponseExt, service_ext::ServiceExt,
+    body_reader::BodyReader,
+    into_uri::IntoUri,
+    no_body::NoBody,
+    request_builder::{
+        ClientRequest, ClientRequestError, InvalidRangeError, InvalidUrgencyError, UriTemplateError,
+    },
+    response_ext::ResponseExt,
+    service_ext::{ExecuteBytesError, ServiceExt},
+    sized_body::SizedBody,
+    try_clone::TryCloneBody,
 };
+#[cfg(feature = "json")]
+pub use self::{request_builder::SendJsonError, response_ext::ApiError};
 
 pub mod body_reader;
+#[cfg(feature = "decompression")]
+pub mod decompress;
+#[cfg(feature = "multipart")]
+pub mod multipart;
 pub mod request_builder;
+pub mod sized_body;
+pub mod streaming_body;
 
 mod into_uri;
+mod no_body;
+#[cfg(any(feature = "rate-limit", feature = "retry"))]
+pub(crate) mod rate_limit;
 mod response_ext;
 mod service_ext;
+pub(crate) mod try_clone;
@@ -0,0 +1,846 @@
+//! Utilities for sending `multipart/form-data` request bodies and reading `multipart/mixed`
+//! and `multipart/byteranges` responses.
+//!
+//! # Example
+//!
+//! ```
+//! use tower_http_client::client::multipart::{Form, Part};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let form = Form::new().text("title", "Report").part(
+//!     "file",
+//!     Part::bytes("id,value\n1,42\n")
+//!         .file_name("report.csv")
+//!         .mime_str("text/csv")?,
+//! );
+//!
+//! let content_type = form.content_type();
+//! assert!(content_type
+//!     .to_str()?
+//!     .starts_with("multipart/form-data; boundary="));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    borrow::Cow,
+    fmt,
+    pin::Pin,
+    str::FromStr,
+    task::{Context, Poll},
+};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_util::{stream, StreamExt as _, TryStreamExt as _};
+use http::{
+    header::{InvalidHeaderValue, CONTENT_RANGE},
+    HeaderMap, HeaderName, HeaderValue, StatusCode,
+};
+use http_body::{Body, Frame, SizeHint};
+use http_body_util::{combinators::UnsyncBoxBody, BodyDataStream, BodyExt as _, StreamBody};
+
+use crate::{random::random_u64, BoxError};
+
+/// A `multipart/form-data` request body builder.
+///
+/// The boundary is randomly generated for each form.
+#[derive(Debug)]
+pub struct Form {
+    boundary: String,
+    parts: Vec<(Cow<'static, str>, Part)>,
+}
+
+/// A single field of the [`Form`].
+pub struct Part {
+    content: PartContent,
+    mime: Option<HeaderValue>,
+    file_name: Option<Cow<'static, str>>,
+}
+
+enum PartContent {
+    Bytes(Bytes),
+    Stream {
+        body: UnsyncBoxBody<Bytes, BoxError>,
+        length: Option<u64>,
+    },
+}
+
+impl Form {
+    /// Creates a new form without fields.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            boundary: generate_boundary(),
+            parts: Vec::new(),
+        }
+    }
+
+    /// Returns the boundary that separates the form fields.
+    #[must_use]
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Adds a text field to the form.
+    #[must_use]
+    pub fn text(
+        self,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.part(name, Part::text(value))
+    }
+
+    /// Adds a customized field to the form.
+    #[must_use]
+    pub fn part(mut self, name: impl Into<Cow<'static, str>>, part: Part) -> Self {
+        self.parts.push((name.into(), part));
+        self
+    }
+
+    /// Returns the `Content-Type` header value for this form.
+    #[must_use]
+    // The boundary consists of hexadecimal digits and dashes only.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn content_type(&self) -> HeaderValue {
+        HeaderValue::try_from(format!("multipart/form-data; boundary={}", self.boundary))
+            .expect("boundary contains only valid header characters")
+    }
+
+    /// Returns the total length of the encoded form if it is known in advance.
+    ///
+    /// The length is known if all of the fields are in-memory or have been created by
+    /// the [`Part::stream_with_length`] method.
+    #[must_use]
+    pub fn content_length(&self) -> Option<u64> {
+        self.parts
+            .iter()
+            .try_fold(0_u64, |length, (name, part)| {
+                let part_length = match &part.content {
+                    PartContent::Bytes(bytes) => bytes.len() as u64,
+                    PartContent::Stream { length, .. } => (*length)?,
+                };
+                let headers_length = self.part_headers(name, part).len() as u64;
+                // Each part ends with the `CRLF` sequence.
+                Some(length + headers_length + part_length + 2)
+            })
+            .map(|length| length + self.closing_boundary().len() as u64)
+    }
+
+    /// Converts this form into the request body.
+    #[must_use]
+    pub fn into_body(self) -> MultipartBody {
+        let content_length = self.content_length();
+        let closing_boundary = self.closing_boundary();
+
+        let part_headers = self
+            .parts
+            .iter()
+            .map(|(name, part)| self.part_headers(name, part))
+            .collect::<Vec<_>>();
+
+        let mut segments = Vec::with_capacity(self.parts.len() * 3 + 1);
+        for ((_, part), headers) in self.parts.into_iter().zip(part_headers) {
+            segments.push(Segment::Bytes(headers));
+            segments.push(match part.content {
+                PartContent::Bytes(bytes) => Segment::Bytes(bytes),
+                PartContent::Stream { body, .. } => Segment::Stream(body),
+            });
+            segments.push(Segment::Bytes(Bytes::from_static(b"\r\n")));
+        }
+        segments.push(Segment::Bytes(closing_boundary));
+
+        // All parts are in-memory, so the form can be encoded at once.
+        if segments
+            .iter()
+            .all(|segment| matches!(segment, Segment::Bytes(_)))
+        {
+            let mut buf = BytesMut::with_capacity(
+                content_length
+                    .and_then(|length| usize::try_from(length).ok())
+                    .unwrap_or_default(),
+            );
+            for segment in segments {
+                if let Segment::Bytes(bytes) = segment {
+                    buf.put(bytes);
+                }
+            }
+            return MultipartBody {
+                inner: Inner::Bytes(Some(buf.freeze())),
+            };
+        }
+
+        let stream = stream::iter(segments)
+            .flat_map(|segment| match segment {
+                Segment::Bytes(bytes) => stream::once(async { Ok(bytes) }).left_stream(),
+                Segment::Stream(body) => BodyDataStream::new(body).right_stream(),
+            })
+            .map_ok(Frame::data);
+        MultipartBody {
+            inner: Inner::Stream {
+                body: StreamBody::new(stream).boxed_unsync(),
+                length: content_length,
+            },
+        }
+    }
+
+    fn part_headers(&self, name: &str, part: &Part) -> Bytes {
+        let mut buf = BytesMut::new();
+        buf.put_slice(b"--");
+        buf.put_slice(self.boundary.as_bytes());
+        buf.put_slice(b"\r\nContent-Disposition: form-data; name=\"");
+        buf.put_slice(escape_quoted(name).as_bytes());
+        buf.put_slice(b"\"");
+        if let Some(file_name) = &part.file_name {
+            buf.put_slice(b"; filename=\"");
+            buf.put_slice(escape_quoted(file_name).as_bytes());
+            buf.put_slice(b"\"");
+        }
+        if let Some(mime) = &part.mime {
+            buf.put_slice(b"\r\nContent-Type: ");
+            buf.put_slice(mime.as_bytes());
+        }
+        buf.put_slice(b"\r\n\r\n");
+        buf.freeze()
+    }
+
+    fn closing_boundary(&self) -> Bytes {
+        Bytes::from(format!("--{}--\r\n", self.boundary))
+    }
+}
+
+impl Default for Form {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Part {
+    /// Creates a new part from the given text.
+    #[must_use]
+    pub fn text(value: impl Into<Cow<'static, str>>) -> Self {
+        let bytes = match value.into() {
+            Cow::Borrowed(value) => Bytes::from_static(value.as_bytes()),
+            Cow::Owned(value) => Bytes::from(value),
+        };
+        Self::new(PartContent::Bytes(bytes))
+    }
+
+    /// Creates a new part from the given bytes.
+    #[must_use]
+    pub fn bytes(value: impl Into<Bytes>) -> Self {
+        Self::new(PartContent::Bytes(value.into()))
+    }
+
+    /// Creates a new part from the given streaming body of unknown length.
+    ///
+    /// A form containing such a part is sent without the `Content-Length` header.
+    #[must_use]
+    pub fn stream<B>(body: B) -> Self
+    where
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        Self::new(PartContent::Stream {
+            body: body.map_err(Into::into).boxed_unsync(),
+            length: None,
+        })
+    }
+
+    /// Creates a new part from the given streaming body of the given length.
+    #[must_use]
+    pub fn stream_with_length<B>(body: B, length: u64) -> Self
+    where
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        Self::new(PartContent::Stream {
+            body: body.map_err(Into::into).boxed_unsync(),
+            length: Some(length),
+        })
+    }
+
+    /// Sets the file name of this part.
+    #[must_use]
+    pub fn file_name(mut self, file_name: impl Into<Cow<'static, str>>) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+
+    /// Sets the `Content-Type` of this part.
+    ///
+    /// # Errors
+    ///
+    /// If the given string is not a valid header value.
+    pub fn mime_str(mut self, mime: &str) -> Result<Self, InvalidHeaderValue> {
+        self.mime = Some(HeaderValue::try_from(mime)?);
+        Ok(self)
+    }
+
+    fn new(content: PartContent) -> Self {
+        Self {
+            content,
+            mime: None,
+            file_name: None,
+        }
+    }
+}
+
+impl fmt::Debug for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Part");
+        match &self.content {
+            PartContent::Bytes(bytes) => debug.field("bytes", &bytes.len()),
+            PartContent::Stream { length, .. } => debug.field("stream", length),
+        };
+        debug
+            .field("mime", &self.mime)
+            .field("file_name", &self.file_name)
+            .finish()
+    }
+}
+
+/// The [`Body`] of the `multipart/form-data` request.
+///
+/// The forms with only in-memory fields are encoded into a single buffer,
+/// and streamed otherwise.
+pub struct MultipartBody {
+    inner: Inner,
+}
+
+enum Inner {
+    Bytes(Option<Bytes>),
+    Stream {
+        body: UnsyncBoxBody<Bytes, BoxError>,
+        length: Option<u64>,
+    },
+}
+
+enum Segment {
+    Bytes(Bytes),
+    Stream(UnsyncBoxBody<Bytes, BoxError>),
+}
+
+impl Body for MultipartBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        match &mut self.inner {
+            Inner::Bytes(bytes) => Poll::Ready(bytes.take().map(|bytes| Ok(Frame::data(bytes)))),
+            Inner::Stream { body, .. } => Pin::new(body).poll_frame(cx),
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        match &self.inner {
+            Inner::Bytes(bytes) => bytes.is_none(),
+            Inner::Stream { body, .. } => body.is_end_stream(),
+        }
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match &self.inner {
+            Inner::Bytes(bytes) => {
+                SizeHint::with_exact(bytes.as_ref().map_or(0, |bytes| bytes.len() as u64))
+            }
+            Inner::Stream {
+                length: Some(length),
+                ..
+            } => SizeHint::with_exact(*length),
+            Inner::Stream { body, .. } => body.size_hint(),
+        }
+    }
+}
+
+impl fmt::Debug for MultipartBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultipartBody").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl From<MultipartBody> for reqwest::Body {
+    fn from(value: MultipartBody) -> Self {
+        match value.inner {
+            Inner::Bytes(bytes) => bytes.unwrap_or_default().into(),
+            Inner::Stream { body, .. } => reqwest::Body::wrap_stream(BodyDataStream::new(body)),
+        }
+    }
+}
+
+/// A single part of the `multipart/mixed` response.
+///
+/// This part is returned by the [`ResponseExt::multipart_responses`] method.
+///
+/// [`ResponseExt::multipart_responses`]: crate::client::ResponseExt::multipart_responses
+#[derive(Debug, Clone)]
+pub struct MixedPart {
+    /// The headers of this part.
+    pub headers: HeaderMap,
+    /// The content of this part.
+    pub body: Bytes,
+}
+
+impl MixedPart {
+    /// Parses the content of this part as an HTTP response.
+    ///
+    /// Batch APIs usually pack each sub-response into a part with the `application/http`
+    /// content type.
+    ///
+    /// # Errors
+    ///
+    /// If the content is not a valid HTTP response.
+    pub fn into_response(self) -> Result<http::Response<Bytes>, MultipartParseError> {
+        let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+        let mut response = httparse::Response::new(&mut headers);
+        let httparse::Status::Complete(length) = response.parse(&self.body)? else {
+            return Err(MultipartParseError::Malformed);
+        };
+        let status = response
+            .code
+            .and_then(|code| StatusCode::from_u16(code).ok())
+            .ok_or(MultipartParseError::Malformed)?;
+
+        let mut builder = http::Response::builder().status(status);
+        if let Some(target) = builder.headers_mut() {
+            *target = header_map(response.headers)?;
+        }
+        builder
+            .body(self.body.slice(length..))
+            .map_err(|_| MultipartParseError::Malformed)
+    }
+}
+
+/// Errors that can occur while parsing the `multipart/mixed` content.
+#[derive(Debug, thiserror::Error)]
+pub enum MultipartParseError {
+    /// The `Content-Type` header is missing or doesn't specify the boundary.
+    #[error("multipart boundary is not specified")]
+    MissingBoundary,
+    /// The content doesn't follow the multipart structure.
+    #[error("malformed multipart content")]
+    Malformed,
+    /// The part headers cannot be parsed.
+    #[error("invalid multipart headers: {0}")]
+    Headers(#[from] httparse::Error),
+}
+
+/// The `Content-Range` header value of a byte range response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+    /// The first byte position of the range.
+    pub start: u64,
+    /// The last byte position of the range, inclusive.
+    pub end: u64,
+    /// The complete length of the resource, if known.
+    pub complete_length: Option<u64>,
+}
+
+impl FromStr for ContentRange {
+    type Err = ByteRangesError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parse = || {
+            let (unit, range) = value.trim().split_once(' ')?;
+            if !unit.eq_ignore_ascii_case("bytes") {
+                return None;
+            }
+            let (range, complete_length) = range.trim().split_once('/')?;
+            let (start, end) = range.split_once('-')?;
+            let range = Self {
+                start: start.parse().ok()?,
+                end: end.parse().ok()?,
+                complete_length: match complete_length {
+                    "*" => None,
+                    length => Some(length.parse().ok()?),
+                },
+            };
+            let valid = range.start <= range.end
+                && range
+                    .complete_length
+                    .map_or(true, |length| range.end < length);
+            valid.then_some(range)
+        };
+        parse().ok_or(ByteRangesError::InvalidContentRange)
+    }
+}
+
+/// Errors that can occur while parsing the `multipart/byteranges` content.
+#[derive(Debug, thiserror::Error)]
+pub enum ByteRangesError {
+    /// The content doesn't follow the multipart structure.
+    #[error(transparent)]
+    Multipart(#[from] MultipartParseError),
+    /// A part has no valid `Content-Range` header or its length doesn't match the header.
+    #[error("invalid byte range part")]
+    InvalidContentRange,
+    /// A returned range overlaps the other one or was not requested.
+    #[error("unexpected byte range {start}-{end}")]
+    UnexpectedRange {
+        /// The first byte position of the range.
+        start: u64,
+        /// The last byte position of the range.
+        end: u64,
+    },
+    /// A requested range is not fully covered by the returned ones.
+    #[error("requested byte range {start}-{end} is missing")]
+    MissingRange {
+        /// The first byte position of the range.
+        start: u64,
+        /// The last byte position of the range.
+        end: u64,
+    },
+}
+
+/// Parses the `multipart/byteranges` content and validates the parts against the requested
+/// inclusive byte ranges.
+///
+/// The server may coalesce the overlapping or adjacent requested ranges, so each requested
+/// range must be contained in a returned one. The returned ranges must not overlap
+/// each other and must intersect at least one requested range.
+///
+/// # Errors
+///
+/// If the content doesn't follow the multipart structure or the returned ranges don't
+/// match the requested ones.
+pub fn parse_byteranges(
+    boundary: &str,
+    body: &Bytes,
+    requested: &[(u64, u64)],
+) -> Result<Vec<(ContentRange, Bytes)>, ByteRangesError> {
+    let ranges = parse_mixed(boundary, body)?
+        .into_iter()
+        .map(|part| {
+            let range = part
+                .headers
+                .get(CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .ok_or(ByteRangesError::InvalidContentRange)?
+                .parse::<ContentRange>()?;
+            let len = range
+                .end
+                .checked_sub(range.start)
+                .and_then(|len| len.checked_add(1));
+            if len != Some(part.body.len() as u64) {
+                return Err(ByteRangesError::InvalidContentRange);
+            }
+            Ok((range, part.body))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut sorted = ranges.iter().map(|(range, _)| range).collect::<Vec<_>>();
+    sorted.sort_by_key(|range| range.start);
+    for pair in sorted.windows(2) {
+        if pair[1].start <= pair[0].end {
+            return Err(ByteRangesError::UnexpectedRange {
+                start: pair[1].start,
+                end: pair[1].end,
+            });
+        }
+    }
+    if let Some(range) = sorted.iter().find(|range| {
+        !requested
+            .iter()
+            .any(|&(start, end)| start <= range.end && range.start <= end)
+    }) {
+        return Err(ByteRangesError::UnexpectedRange {
+            start: range.start,
+            end: range.end,
+        });
+    }
+    if let Some(&(start, end)) = requested.iter().find(|&&(start, end)| {
+        !sorted
+            .iter()
+            .any(|range| range.start <= start && end <= range.end)
+    }) {
+        return Err(ByteRangesError::MissingRange { start, end });
+    }
+
+    Ok(ranges)
+}
+
+/// The maximum number of headers in a single part.
+const MAX_HEADERS: usize = 64;
+
+/// Splits the `multipart/mixed` content into the parts using the given boundary.
+///
+/// # Errors
+///
+/// If the content doesn't follow the multipart structure.
+pub fn parse_mixed(boundary: &str, body: &Bytes) -> Result<Vec<MixedPart>, MultipartParseError> {
+    let delimiter = format!("--{boundary}");
+    let separator = format!("\r\n{delimiter}");
+
+    // Skip the preamble.
+    let mut position = if body.starts_with(delimiter.as_bytes()) {
+        delimiter.len()
+    } else {
+        find(body, separator.as_bytes(), 0).ok_or(MultipartParseError::Malformed)? + separator.len()
+    };
+
+    let mut parts = Vec::new();
+    loop {
+        if body[position..].starts_with(b"--") {
+            return Ok(parts);
+        }
+        // Skip the transport padding after the delimiter.
+        while body
+            .get(position)
+            .is_some_and(|c| *c == b' ' || *c == b'\t')
+        {
+            position += 1;
+        }
+        if !body[position..].starts_with(b"\r\n") {
+            return Err(MultipartParseError::Malformed);
+        }
+        position += 2;
+
+        let end =
+            find(body, separator.as_bytes(), position).ok_or(MultipartParseError::Malformed)?;
+        parts.push(parse_part(&body.slice(position..end))?);
+        position = end + separator.len();
+    }
+}
+
+fn parse_part(part: &Bytes) -> Result<MixedPart, MultipartParseError> {
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let httparse::Status::Complete((length, headers)) =
+        httparse::parse_headers(part, &mut headers)?
+    else {
+        return Err(MultipartParseError::Malformed);
+    };
+
+    Ok(MixedPart {
+        headers: header_map(headers)?,
+        body: part.slice(length..),
+    })
+}
+
+fn header_map(headers: &[httparse::Header<'_>]) -> Result<HeaderMap, MultipartParseError> {
+    let mut map = HeaderMap::with_capacity(headers.len());
+    for header in headers {
+        let name = HeaderName::from_bytes(header.name.as_bytes())
+            .map_err(|_| MultipartParseError::Headers(httparse::Error::HeaderName))?;
+        let value = HeaderValue::from_bytes(header.value)
+            .map_err(|_| MultipartParseError::Headers(httparse::Error::HeaderValue))?;
+        map.append(name, value);
+    }
+    Ok(map)
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| position + from)
+}
+
+// Escapes the quoted string in the same way as browsers do.
+fn escape_quoted(value: &str) -> Cow<'_, str> {
+    if value.contains(['"', '\r', '\n']) {
+        Cow::Owned(
+            value
+                .replace('"', "%22")
+                .replace('\r', "%0D")
+                .replace('\n', "%0A"),
+        )
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+fn generate_boundary() -> String {
+    format!(
+        "{:016x}-{:016x}-{:016x}-{:016x}",
+        random_u64(),
+        random_u64(),
+        random_u64(),
+        random_u64()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use http_body::Body as _;
+    use http_body_util::{BodyExt as _, Full};
+    use pretty_assertions::assert_eq;
+
+    use super::{
+        parse_byteranges, parse_mixed, ByteRangesError, ContentRange, Form, MultipartParseError,
+        Part,
+    };
+
+    #[tokio::test]
+    async fn test_multipart_form_in_memory() -> anyhow::Result<()> {
+        let form = Form::new().text("title", "Report").part(
+            "file",
+            Part::bytes("1,42")
+                .file_name("my \"report\".csv")
+                .mime_str("text/csv")?,
+        );
+        let boundary = form.boundary().to_owned();
+        let content_length = form.content_length();
+
+        let body = form.into_body();
+        assert_eq!(body.size_hint().exact(), content_length);
+
+        let bytes = body.collect().await.unwrap().to_bytes();
+        let expected = format!(
+            "--{boundary}\r\n\
+             Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+             Report\r\n\
+             --{boundary}\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"my %22report%22.csv\"\r\n\
+             Content-Type: text/csv\r\n\r\n\
+             1,42\r\n\
+             --{boundary}--\r\n"
+        );
+        assert_eq!(std::str::from_utf8(&bytes)?, expected);
+        assert_eq!(content_length, Some(expected.len() as u64));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_multipart_form_stream() -> anyhow::Result<()> {
+        let form = Form::new()
+            .text("title", "Report")
+            .part("file", Part::stream(Full::new(Bytes::from_static(b"1,42"))));
+        let boundary = form.boundary().to_owned();
+        assert_eq!(form.content_length(), None);
+
+        let bytes = form.into_body().collect().await.unwrap().to_bytes();
+        let expected = format!(
+            "--{boundary}\r\n\
+             Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+             Report\r\n\
+             --{boundary}\r\n\
+             Content-Disposition: form-data; name=\"file\"\r\n\r\n\
+             1,42\r\n\
+             --{boundary}--\r\n"
+        );
+        assert_eq!(std::str::from_utf8(&bytes)?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multipart_boundary_is_random() {
+        assert_ne!(Form::new().boundary(), Form::new().boundary());
+    }
+
+    #[test]
+    fn test_multipart_parse_mixed() -> anyhow::Result<()> {
+        let body = Bytes::from_static(
+            b"This is the preamble.\r\n\
+              --batch\r\n\
+              Content-Type: application/http\r\n\
+              Content-ID: <response-1>\r\n\r\n\
+              HTTP/1.1 200 OK\r\n\
+              Content-Type: application/json\r\n\r\n\
+              {\"id\":1}\r\n\
+              --batch \r\n\
+              \r\n\
+              plain part\r\n\
+              --batch--\r\n\
+              epilogue",
+        );
+
+        let parts = parse_mixed("batch", &body)?;
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].headers["content-id"], "<response-1>");
+        assert!(parts[1].headers.is_empty());
+        assert_eq!(parts[1].body, "plain part");
+
+        let response = parts[0].clone().into_response()?;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        assert_eq!(response.into_body(), "{\"id\":1}");
+
+        assert!(matches!(
+            parse_mixed("other", &body),
+            Err(MultipartParseError::Malformed)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_multipart_parse_byteranges() -> anyhow::Result<()> {
+        let body = Bytes::from_static(
+            b"--SEP\r\n\
+              Content-Type: text/plain\r\n\
+              Content-Range: bytes 0-4/20\r\n\r\n\
+              Hello\r\n\
+              --SEP\r\n\
+              Content-Range: bytes 10-14/20\r\n\r\n\
+              world\r\n\
+              --SEP--\r\n",
+        );
+
+        let parts = parse_byteranges("SEP", &body, &[(0, 4), (11, 14)])?;
+        assert_eq!(parts.len(), 2);
+        assert_eq!(
+            parts[1].0,
+            ContentRange {
+                start: 10,
+                end: 14,
+                complete_length: Some(20),
+            }
+        );
+        assert_eq!(parts[0].1, "Hello");
+        assert_eq!(parts[1].1, "world");
+
+        assert!(matches!(
+            parse_byteranges("SEP", &body, &[(0, 4), (10, 15)]),
+            Err(ByteRangesError::MissingRange { start: 10, end: 15 })
+        ));
+        assert!(matches!(
+            parse_byteranges("SEP", &body, &[(0, 4)]),
+            Err(ByteRangesError::UnexpectedRange { start: 10, end: 14 })
+        ));
+
+        let body = Bytes::from_static(
+            b"--SEP\r\n\
+              Content-Range: bytes 0-4/*\r\n\r\n\
+              Hello\r\n\
+              --SEP\r\n\
+              Content-Range: bytes 3-4/*\r\n\r\n\
+              lo\r\n\
+              --SEP--\r\n",
+        );
+        assert!(matches!(
+            parse_byteranges("SEP", &body, &[(0, 4)]),
+            Err(ByteRangesError::UnexpectedRange { start: 3, end: 4 })
+        ));
+
+        let body = Bytes::from_static(
+            b"--SEP\r\n\
+              Content-Range: bytes 0-9/20\r\n\r\n\
+              Hello\r\n\
+              --SEP--\r\n",
+        );
+        assert!(matches!(
+            parse_byteranges("SEP", &body, &[(0, 9)]),
+            Err(ByteRangesError::InvalidContentRange)
+        ));
+
+        // The range length overflows.
+        let body = Bytes::from_static(
+            b"--SEP\r\n\
+              Content-Range: bytes 0-18446744073709551615/*\r\n\r\n\
+              Hello\r\n\
+              --SEP--\r\n",
+        );
+        assert!(matches!(
+            parse_byteranges("SEP", &body, &[(0, 4)]),
+            Err(ByteRangesError::InvalidContentRange)
+        ));
+        Ok(())
+    }
+}
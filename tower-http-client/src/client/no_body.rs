@@ -0,0 +1,122 @@
+use std::{
+    convert::Infallible,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::{Buf, Bytes};
+use http_body::{Body, Frame, SizeHint};
+
+/// An empty request body, which converts into the common body types.
+///
+/// The `()` type cannot be converted into the foreign body types due to the orphan rules,
+/// so use this type to construct the requests without a body independently of the
+/// request body type expected by the client. It converts into the following types:
+///
+/// - [`reqwest::Body`] (`reqwest` feature).
+/// - [`Full`], [`Empty`], [`BoxBody`] and [`UnsyncBoxBody`] from the [`http-body-util`]
+///   crate.
+/// - [`Bytes`], [`Vec<u8>`] and [`String`].
+///
+/// The [`ClientRequest`] builders created by the [`ServiceExt`] methods already start with
+/// an empty body of the client body type, so this type is mostly useful for the requests
+/// passed to the [`ServiceExt::execute`] method.
+///
+/// # Example
+///
+/// ```no_run
+/// use tower_http_client::{client::NoBody, ServiceExt as _};
+/// use tower_reqwest::HttpClientService;
+///
+/// #[tokio::main]
+/// async fn main() -> anyhow::Result<()> {
+///     let mut client = HttpClientService::new(reqwest::Client::new());
+///
+///     let request = http::Request::get("http://ip.jsontest.com").body(NoBody)?;
+///     let response = client.execute(request).await?;
+///     println!("{}", response.status());
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// [`reqwest::Body`]: https://docs.rs/reqwest/latest/reqwest/struct.Body.html
+/// [`Full`]: http_body_util::Full
+/// [`Empty`]: http_body_util::Empty
+/// [`BoxBody`]: http_body_util::combinators::BoxBody
+/// [`UnsyncBoxBody`]: http_body_util::combinators::UnsyncBoxBody
+/// [`http-body-util`]: https://docs.rs/http-body-util
+/// [`ClientRequest`]: super::ClientRequest
+/// [`ServiceExt`]: super::ServiceExt
+/// [`ServiceExt::execute`]: super::ServiceExt::execute
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoBody;
+
+impl Body for NoBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        Poll::Ready(None)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        true
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(0)
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl From<NoBody> for reqwest::Body {
+    fn from(_: NoBody) -> Self {
+        Self::default()
+    }
+}
+
+impl<D: Buf> From<NoBody> for http_body_util::Full<D> {
+    fn from(_: NoBody) -> Self {
+        Self::default()
+    }
+}
+
+impl<D: Buf> From<NoBody> for http_body_util::Empty<D> {
+    fn from(_: NoBody) -> Self {
+        Self::new()
+    }
+}
+
+impl<D: Buf + 'static, E> From<NoBody> for http_body_util::combinators::BoxBody<D, E> {
+    fn from(_: NoBody) -> Self {
+        Self::default()
+    }
+}
+
+impl<D: Buf + 'static, E> From<NoBody> for http_body_util::combinators::UnsyncBoxBody<D, E> {
+    fn from(_: NoBody) -> Self {
+        Self::default()
+    }
+}
+
+impl From<NoBody> for Bytes {
+    fn from(_: NoBody) -> Self {
+        Self::new()
+    }
+}
+
+impl From<NoBody> for Vec<u8> {
+    fn from(_: NoBody) -> Self {
+        Self::new()
+    }
+}
+
+impl From<NoBody> for String {
+    fn from(_: NoBody) -> Self {
+        Self::new()
+    }
+}
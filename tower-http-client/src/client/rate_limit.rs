@@ -0,0 +1,292 @@
+//! Parsers for the `Retry-After` and rate limit response headers.
+
+use std::time::{Duration, SystemTime};
+
+use http::{header::RETRY_AFTER, HeaderMap};
+#[cfg(feature = "rate-limit")]
+use http::{HeaderName, HeaderValue};
+
+/// The rate limit quota advertised by the response headers.
+///
+/// The fields are `None` if the corresponding values are absent or malformed.
+#[cfg(feature = "rate-limit")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rate-limit")))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RateLimit {
+    /// The maximum number of requests in the quota window.
+    pub limit: Option<u64>,
+    /// The number of requests remaining in the current window.
+    pub remaining: Option<u64>,
+    /// The time until the quota resets.
+    pub reset: Option<Duration>,
+    /// The length of the quota window, taken from the `RateLimit-Policy` header.
+    pub window: Option<Duration>,
+}
+
+/// The header names to read the rate limit quota from.
+///
+/// By default, the `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset`
+/// headers and their `RateLimit-*` counterparts are read. The vendor specific names can
+/// be added by the builder methods, they take precedence over the default ones.
+///
+/// Additionally, the structured `RateLimit` and `RateLimit-Policy` headers defined by
+/// the IETF draft are always read, for example, `RateLimit: limit=100, remaining=50, reset=30`
+/// or `RateLimit: "default";r=50;t=30` with `RateLimit-Policy: "default";q=100;w=60`.
+///
+/// The reset value is either a number of seconds, a Unix timestamp or an HTTP date.
+#[cfg(feature = "rate-limit")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rate-limit")))]
+#[derive(Debug, Clone)]
+pub struct RateLimitHeaders {
+    limit: Vec<HeaderName>,
+    remaining: Vec<HeaderName>,
+    reset: Vec<HeaderName>,
+}
+
+#[cfg(feature = "rate-limit")]
+impl RateLimitHeaders {
+    /// Creates the default header names.
+    #[must_use]
+    pub fn new() -> Self {
+        let names = |names: [&'static str; 2]| names.map(HeaderName::from_static).to_vec();
+        Self {
+            limit: names(["x-ratelimit-limit", "ratelimit-limit"]),
+            remaining: names(["x-ratelimit-remaining", "ratelimit-remaining"]),
+            reset: names(["x-ratelimit-reset", "ratelimit-reset"]),
+        }
+    }
+
+    /// Adds the header name to read the quota limit from.
+    #[must_use]
+    pub fn limit(mut self, name: HeaderName) -> Self {
+        self.limit.insert(0, name);
+        self
+    }
+
+    /// Adds the header name to read the remaining requests number from.
+    #[must_use]
+    pub fn remaining(mut self, name: HeaderName) -> Self {
+        self.remaining.insert(0, name);
+        self
+    }
+
+    /// Adds the header name to read the quota reset time from.
+    #[must_use]
+    pub fn reset(mut self, name: HeaderName) -> Self {
+        self.reset.insert(0, name);
+        self
+    }
+}
+
+#[cfg(feature = "rate-limit")]
+impl Default for RateLimitHeaders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses the rate limit headers, returns `None` if there are none of them.
+#[cfg(feature = "rate-limit")]
+pub(crate) fn rate_limit(
+    headers: &HeaderMap,
+    names: &RateLimitHeaders,
+    now: SystemTime,
+) -> Option<RateLimit> {
+    let mut rate_limit = RateLimit::default();
+
+    // The structured fields have the `key=value` parameters separated by commas or semicolons.
+    let params = |name: &'static str| {
+        headers
+            .get_all(name)
+            .into_iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split([',', ';']))
+            .map(str::trim)
+    };
+    for param in params("ratelimit") {
+        let Some((key, value)) = param.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "limit" => rate_limit.limit = rate_limit.limit.or(value.trim().parse().ok()),
+            "remaining" | "r" => {
+                rate_limit.remaining = rate_limit.remaining.or(value.trim().parse().ok());
+            }
+            "reset" | "t" => rate_limit.reset = rate_limit.reset.or(parse_reset(value, now)),
+            _ => {}
+        }
+    }
+    for param in params("ratelimit-policy") {
+        match param.split_once('=') {
+            Some(("q", value)) => rate_limit.limit = rate_limit.limit.or(value.parse().ok()),
+            Some(("w", value)) => {
+                rate_limit.window = rate_limit
+                    .window
+                    .or(value.parse().ok().map(Duration::from_secs));
+            }
+            // The older draft starts the policy with the bare quota limit.
+            None => rate_limit.limit = rate_limit.limit.or(param.parse().ok()),
+            _ => {}
+        }
+    }
+
+    let first = |names: &[HeaderName]| {
+        names
+            .iter()
+            .find_map(|name| headers.get(name))
+            .and_then(|value: &HeaderValue| value.to_str().ok())
+    };
+    rate_limit.limit = rate_limit
+        .limit
+        .or_else(|| first(&names.limit)?.trim().parse().ok());
+    rate_limit.remaining = rate_limit
+        .remaining
+        .or_else(|| first(&names.remaining)?.trim().parse().ok());
+    rate_limit.reset = rate_limit
+        .reset
+        .or_else(|| parse_reset(first(&names.reset)?, now));
+
+    (rate_limit != RateLimit::default()).then_some(rate_limit)
+}
+
+// Parses the reset time given as the delay, the Unix timestamp or the HTTP date.
+#[cfg(feature = "rate-limit")]
+fn parse_reset(value: &str, now: SystemTime) -> Option<Duration> {
+    // The delays are never that large, so such numbers are the Unix timestamps.
+    const MIN_TIMESTAMP: u64 = 1_000_000_000;
+
+    let value = value.trim();
+    let date = match value.parse::<u64>() {
+        Ok(seconds) if seconds < MIN_TIMESTAMP => return Some(Duration::from_secs(seconds)),
+        Ok(timestamp) => SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(timestamp))?,
+        Err(_) => httpdate::parse_http_date(value).ok()?,
+    };
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// Parses the `Retry-After` header value as the delay from the given time.
+pub(crate) fn retry_after(headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use http::{header::RETRY_AFTER, HeaderMap, HeaderValue};
+
+    use super::retry_after;
+
+    #[test]
+    fn test_retry_after() {
+        let now = SystemTime::now();
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers, now), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(120)));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers, now), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(retry_after(&headers, now), None);
+    }
+
+    #[cfg(feature = "rate-limit")]
+    #[test]
+    fn test_rate_limit() {
+        use http::HeaderName;
+
+        use super::{rate_limit, RateLimit, RateLimitHeaders};
+
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let parse = |pairs: &[(&'static str, &'static str)], names: &RateLimitHeaders| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.append(*name, HeaderValue::from_static(value));
+            }
+            rate_limit(&headers, names, now)
+        };
+        let names = RateLimitHeaders::new();
+        let quota = |limit, remaining, reset: Option<u64>, window: Option<u64>| RateLimit {
+            limit,
+            remaining,
+            reset: reset.map(Duration::from_secs),
+            window: window.map(Duration::from_secs),
+        };
+
+        assert_eq!(parse(&[], &names), None);
+        // The reset is given as the Unix timestamp.
+        let headers = [
+            ("x-ratelimit-limit", "60"),
+            ("x-ratelimit-remaining", "59"),
+            ("x-ratelimit-reset", "1700000030"),
+        ];
+        assert_eq!(
+            parse(&headers, &names),
+            Some(quota(Some(60), Some(59), Some(30), None))
+        );
+        // The reset is given as the HTTP date in the past.
+        let headers = [
+            ("ratelimit-remaining", "0"),
+            ("ratelimit-reset", "Wed, 21 Oct 2015 07:28:00 GMT"),
+        ];
+        assert_eq!(
+            parse(&headers, &names),
+            Some(quota(None, Some(0), Some(0), None))
+        );
+        // The structured fields of both drafts.
+        let headers = [("ratelimit", "limit=100, remaining=50, reset=30")];
+        assert_eq!(
+            parse(&headers, &names),
+            Some(quota(Some(100), Some(50), Some(30), None))
+        );
+        let headers = [
+            ("ratelimit", "\"default\";r=50;t=30"),
+            ("ratelimit-policy", "\"default\";q=100;w=60"),
+        ];
+        assert_eq!(
+            parse(&headers, &names),
+            Some(quota(Some(100), Some(50), Some(30), Some(60)))
+        );
+        // The timestamp beyond the system time range is ignored.
+        let headers = [
+            ("x-ratelimit-remaining", "1"),
+            ("x-ratelimit-reset", "18446744073709551615"),
+        ];
+        assert_eq!(
+            parse(&headers, &names),
+            Some(quota(None, Some(1), None, None))
+        );
+        let headers = [("ratelimit-policy", "10;w=1")];
+        assert_eq!(
+            parse(&headers, &names),
+            Some(quota(Some(10), None, None, Some(1)))
+        );
+
+        // The vendor headers take precedence over the default ones.
+        let names = RateLimitHeaders::new()
+            .remaining(HeaderName::from_static("x-quota-left"))
+            .reset(HeaderName::from_static("x-quota-reset"));
+        let headers = [
+            ("x-ratelimit-remaining", "5"),
+            ("x-quota-left", "7"),
+            ("x-quota-reset", "15"),
+        ];
+        assert_eq!(
+            parse(&headers, &names),
+            Some(quota(None, Some(7), Some(15), None))
+        );
+    }
+}
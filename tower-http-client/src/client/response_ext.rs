@@ -25,10 +25,601 @@ pub trait ResponseExt<T>: Sized {
     /// ```
     ///
     fn body_reader(self) -> BodyReader<T>;
+
+    /// Consumes the response and reads its full body as [`Bytes`].
+    ///
+    /// It is a shortcut for the [`BodyReader::bytes`] method.
+    ///
+    /// [`Bytes`]: bytes::Bytes
+    fn bytes(self) -> impl std::future::Future<Output = Result<bytes::Bytes, T::Error>>
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf;
+
+    /// Consumes the response and reads its full body as UTF-8 text.
+    ///
+    /// It is a shortcut for the [`BodyReader::utf8`] method.
+    fn utf8(
+        self,
+    ) -> impl std::future::Future<
+        Output = Result<
+            String,
+            super::body_reader::BodyReaderError<T::Error, std::string::FromUtf8Error>,
+        >,
+    >
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf;
+
+    /// Consumes the response and reads its body to the end discarding the data.
+    ///
+    /// Dropping the response with the unread body prevents the connection from being
+    /// returned to the pool, so use this method if only the status or headers are needed.
+    /// Unlike the [`bytes`](Self::bytes) method, the data is not buffered.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use tower_http_client::{ResponseExt as _, ServiceExt as _};
+    /// use tower_reqwest::HttpClientService;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let mut client = HttpClientService::new(reqwest::Client::new());
+    ///     let response = client.get("http://example.com").send()?.await?;
+    ///
+    ///     let status = response.status();
+    ///     response.drain().await?;
+    ///     println!("{status}");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn drain(self) -> impl std::future::Future<Output = Result<(), T::Error>>
+    where
+        T: http_body::Body;
+
+    /// Returns the typed header of this response.
+    ///
+    /// Returns `None` if the header is absent or malformed, use the
+    /// [`try_typed_header`](Self::try_typed_header) method to distinguish these cases.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use headers::ContentType;
+    /// use tower_http_client::ResponseExt as _;
+    ///
+    /// let response = http::Response::builder()
+    ///     .header(http::header::CONTENT_TYPE, "application/json")
+    ///     .body(())
+    ///     .unwrap();
+    ///
+    /// assert_eq!(response.typed_header(), Some(ContentType::json()));
+    /// ```
+    #[cfg(feature = "typed-header")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "typed-header")))]
+    fn typed_header<H: headers::Header>(&self) -> Option<H>;
+
+    /// Tries to return the typed header of this response.
+    ///
+    /// Returns `Ok(None)` if the header is absent.
+    ///
+    /// # Errors
+    ///
+    /// If the header is malformed.
+    #[cfg(feature = "typed-header")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "typed-header")))]
+    fn try_typed_header<H: headers::Header>(&self) -> Result<Option<H>, headers::Error>;
+
+    /// Returns the address of the server which sent this response.
+    ///
+    /// Returns `None` if the client adapter didn't provide the [`RemoteAddr`] extension,
+    /// for example, if the response was not received via the HTTP connector.
+    ///
+    /// [`RemoteAddr`]: tower_reqwest::RemoteAddr
+    #[cfg(any(feature = "hyper", feature = "reqwest"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "hyper", feature = "reqwest"))))]
+    fn remote_addr(&self) -> Option<std::net::SocketAddr>;
+
+    /// Returns the rate limit quota advertised by the response headers.
+    ///
+    /// Returns `None` if the response has no rate limit headers. See the
+    /// [`RateLimitHeaders`] for the list of the supported headers, use the
+    /// [`rate_limit_with`](Self::rate_limit_with) method to read the vendor specific ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tower_http_client::ResponseExt as _;
+    ///
+    /// let response = http::Response::builder()
+    ///     .header("x-ratelimit-limit", "60")
+    ///     .header("x-ratelimit-remaining", "0")
+    ///     .header("x-ratelimit-reset", "30")
+    ///     .body(())
+    ///     .unwrap();
+    ///
+    /// let rate_limit = response.rate_limit().unwrap();
+    /// assert_eq!(rate_limit.remaining, Some(0));
+    /// assert_eq!(rate_limit.reset, Some(std::time::Duration::from_secs(30)));
+    /// ```
+    ///
+    /// [`RateLimitHeaders`]: super::RateLimitHeaders
+    #[cfg(feature = "rate-limit")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rate-limit")))]
+    fn rate_limit(&self) -> Option<super::RateLimit>;
+
+    /// Returns the rate limit quota advertised by the given response headers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use http::HeaderName;
+    /// use tower_http_client::{client::RateLimitHeaders, ResponseExt as _};
+    ///
+    /// let response = http::Response::builder()
+    ///     .header("x-quota-remaining", "10")
+    ///     .body(())
+    ///     .unwrap();
+    ///
+    /// let names = RateLimitHeaders::new().remaining(HeaderName::from_static("x-quota-remaining"));
+    /// let rate_limit = response.rate_limit_with(&names).unwrap();
+    /// assert_eq!(rate_limit.remaining, Some(10));
+    /// ```
+    #[cfg(feature = "rate-limit")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rate-limit")))]
+    fn rate_limit_with(&self, names: &super::RateLimitHeaders) -> Option<super::RateLimit>;
+
+    /// Returns the delay requested by the `Retry-After` response header.
+    ///
+    /// Both the number of seconds and the HTTP date forms are supported, the dates in
+    /// the past produce the zero delay.
+    #[cfg(feature = "rate-limit")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rate-limit")))]
+    fn retry_after(&self) -> Option<std::time::Duration>;
+
+    /// Consumes the response and splits its `multipart/mixed` body into the parts.
+    ///
+    /// The boundary is taken from the response `Content-Type` header. Use the
+    /// [`MixedPart::into_response`] method to parse the sub-responses returned by
+    /// the batch APIs.
+    ///
+    /// [`MixedPart::into_response`]: super::multipart::MixedPart::into_response
+    #[cfg(feature = "multipart")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "multipart")))]
+    fn multipart_responses(
+        self,
+    ) -> impl std::future::Future<
+        Output = Result<
+            Vec<super::multipart::MixedPart>,
+            super::body_reader::BodyReaderError<T::Error, super::multipart::MultipartParseError>,
+        >,
+    >
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf;
+
+    /// Consumes the response and decompresses its body according to the `Content-Encoding`
+    /// header.
+    ///
+    /// Unlike the `Content-Length` header, which reflects the compressed size, the `limit`
+    /// restricts the decompressed body length. The body is decoded as its chunks are received,
+    /// and the decoding stops with the [`DecompressError::DecompressedTooLarge`] error before
+    /// exceeding the limit, so reading untrusted responses is safe against the decompression
+    /// bombs. The compressed body is limited by the same number of bytes.
+    ///
+    /// The body of the response with the [`NoDecompress`] extension is returned as is.
+    ///
+    /// See the [`decompress`] function for the supported content codings.
+    ///
+    /// [`DecompressError::DecompressedTooLarge`]: super::decompress::DecompressError::DecompressedTooLarge
+    /// [`decompress`]: super::decompress::decompress
+    /// [`NoDecompress`]: super::decompress::NoDecompress
+    #[cfg(feature = "decompression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "decompression")))]
+    fn decompressed(
+        self,
+        limit: usize,
+    ) -> impl std::future::Future<
+        Output = Result<
+            bytes::Bytes,
+            super::body_reader::BodyReaderError<T::Error, super::decompress::DecompressError>,
+        >,
+    >
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf;
+
+    /// Consumes the response and reads its text using the charset from the `Content-Type`
+    /// header.
+    ///
+    /// See the [`BodyReader::text`] method for more details.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use tower_http_client::{ResponseExt as _, ServiceExt as _};
+    /// use tower_reqwest::HttpClientService;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let mut client = HttpClientService::new(reqwest::Client::new());
+    ///     let response = client.get("http://example.com").send()?.await?;
+    ///
+    ///     let text = response.text().await?;
+    ///     println!("{text}");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "charset")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "charset")))]
+    fn text(
+        self,
+    ) -> impl std::future::Future<
+        Output = Result<
+            String,
+            super::body_reader::BodyReaderError<T::Error, super::body_reader::UnknownCharsetError>,
+        >,
+    >
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf;
+
+    /// Consumes the response and turns it into a typed API result.
+    ///
+    /// If the response status is successful, the body is deserialized as `R`,
+    /// otherwise it is deserialized as `E` and returned as the [`ApiError::Status`] error
+    /// along with the response status. The response `Content-Type` header, if present,
+    /// must describe JSON, otherwise the [`ApiError::ContentType`] error is returned
+    /// without reading the body.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use serde::Deserialize;
+    /// use tower_http_client::{client::ApiError, ResponseExt as _, ServiceExt as _};
+    /// use tower_reqwest::HttpClientService;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Ip {
+    ///     ip: String,
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Problem {
+    ///     message: String,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let mut client = HttpClientService::new(reqwest::Client::new());
+    ///     let response = client.get("http://ip.jsontest.com").send()?.await?;
+    ///
+    ///     match response.into_api_result::<Ip, Problem>().await {
+    ///         Ok(ip) => println!("{}", ip.ip),
+    ///         Err(ApiError::Status { status, body }) => println!("{status}: {}", body.message),
+    ///         Err(err) => return Err(err.into()),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    fn into_api_result<R, E>(
+        self,
+    ) -> impl std::future::Future<Output = Result<R, ApiError<E, T::Error>>>
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf,
+        R: serde::de::DeserializeOwned,
+        E: serde::de::DeserializeOwned;
+
+    /// Consumes the response and deserializes its body as an optional JSON value.
+    ///
+    /// Returns `None` for the `204 No Content` and `205 Reset Content` responses and for
+    /// the empty bodies, so the endpoints which omit the response object don't need to be
+    /// special-cased.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use serde::Deserialize;
+    /// use tower_http_client::{ResponseExt as _, ServiceExt as _};
+    /// use tower_reqwest::HttpClientService;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Ip {
+    ///     ip: String,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let mut client = HttpClientService::new(reqwest::Client::new());
+    ///     let response = client.get("http://ip.jsontest.com").send()?.await?;
+    ///
+    ///     if let Some(ip) = response.json_optional::<Ip>().await? {
+    ///         println!("{}", ip.ip);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    fn json_optional<R>(
+        self,
+    ) -> impl std::future::Future<
+        Output = Result<
+            Option<R>,
+            super::body_reader::BodyReaderError<T::Error, serde_json::Error>,
+        >,
+    >
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf,
+        R: serde::de::DeserializeOwned;
+}
+
+/// Errors returned by the [`ResponseExt::into_api_result`] method.
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError<E, B> {
+    /// The server responded with an unsuccessful status and the decoded error body.
+    #[error("server responded with the unsuccessful status {status}")]
+    Status {
+        /// The response status.
+        status: http::StatusCode,
+        /// The decoded error body.
+        body: E,
+    },
+    /// The response `Content-Type` header doesn't describe JSON.
+    #[error("unexpected response content type {content_type:?} with status {status}")]
+    ContentType {
+        /// The response status.
+        status: http::StatusCode,
+        /// The response `Content-Type` header value.
+        content_type: http::HeaderValue,
+    },
+    /// An error occurred while reading or decoding the response body.
+    #[error("cannot read the response body with status {status}")]
+    Body {
+        /// The response status.
+        status: http::StatusCode,
+        /// The body reading or decoding error.
+        #[source]
+        source: super::body_reader::BodyReaderError<B, serde_json::Error>,
+    },
+}
+
+#[cfg(feature = "json")]
+impl<E, B> ApiError<E, B> {
+    /// Returns the response status.
+    pub const fn status(&self) -> http::StatusCode {
+        match self {
+            Self::Status { status, .. }
+            | Self::ContentType { status, .. }
+            | Self::Body { status, .. } => *status,
+        }
+    }
 }
 
 impl<T> ResponseExt<T> for http::Response<T> {
     fn body_reader(self) -> BodyReader<T> {
         BodyReader::new(self.into_body())
     }
+
+    async fn bytes(self) -> Result<bytes::Bytes, T::Error>
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf,
+    {
+        self.body_reader().bytes().await
+    }
+
+    async fn utf8(
+        self,
+    ) -> Result<String, super::body_reader::BodyReaderError<T::Error, std::string::FromUtf8Error>>
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf,
+    {
+        self.body_reader().utf8().await
+    }
+
+    async fn drain(self) -> Result<(), T::Error>
+    where
+        T: http_body::Body,
+    {
+        use http_body_util::BodyExt as _;
+
+        let mut body = std::pin::pin!(self.into_body());
+        while let Some(frame) = body.frame().await {
+            frame?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "typed-header")]
+    fn typed_header<H: headers::Header>(&self) -> Option<H> {
+        headers::HeaderMapExt::typed_get(self.headers())
+    }
+
+    #[cfg(feature = "typed-header")]
+    fn try_typed_header<H: headers::Header>(&self) -> Result<Option<H>, headers::Error> {
+        headers::HeaderMapExt::typed_try_get(self.headers())
+    }
+
+    #[cfg(feature = "rate-limit")]
+    fn rate_limit(&self) -> Option<super::RateLimit> {
+        self.rate_limit_with(&super::RateLimitHeaders::new())
+    }
+
+    #[cfg(feature = "rate-limit")]
+    fn rate_limit_with(&self, names: &super::RateLimitHeaders) -> Option<super::RateLimit> {
+        super::rate_limit::rate_limit(self.headers(), names, std::time::SystemTime::now())
+    }
+
+    #[cfg(feature = "rate-limit")]
+    fn retry_after(&self) -> Option<std::time::Duration> {
+        super::rate_limit::retry_after(self.headers(), std::time::SystemTime::now())
+    }
+
+    #[cfg(any(feature = "hyper", feature = "reqwest"))]
+    fn remote_addr(&self) -> Option<std::net::SocketAddr> {
+        self.extensions()
+            .get::<tower_reqwest::RemoteAddr>()
+            .map(|remote_addr| remote_addr.0)
+    }
+
+    #[cfg(feature = "multipart")]
+    async fn multipart_responses(
+        self,
+    ) -> Result<
+        Vec<super::multipart::MixedPart>,
+        super::body_reader::BodyReaderError<T::Error, super::multipart::MultipartParseError>,
+    >
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf,
+    {
+        use super::{body_reader::BodyReaderError, multipart::MultipartParseError};
+
+        let boundary = self
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| crate::mime::parameter(value, "boundary"))
+            .map(ToOwned::to_owned)
+            .ok_or(BodyReaderError::Decode(
+                MultipartParseError::MissingBoundary,
+            ))?;
+
+        let bytes = self
+            .body_reader()
+            .bytes()
+            .await
+            .map_err(BodyReaderError::Read)?;
+        super::multipart::parse_mixed(&boundary, &bytes).map_err(BodyReaderError::Decode)
+    }
+
+    #[cfg(feature = "decompression")]
+    async fn decompressed(
+        self,
+        limit: usize,
+    ) -> Result<
+        bytes::Bytes,
+        super::body_reader::BodyReaderError<T::Error, super::decompress::DecompressError>,
+    >
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf,
+    {
+        use futures_util::StreamExt as _;
+
+        use super::body_reader::BodyReaderError;
+
+        let encoding = if self.extensions().get::<super::NoDecompress>().is_some() {
+            String::new()
+        } else {
+            self.headers()
+                .get_all(http::header::CONTENT_ENCODING)
+                .iter()
+                .map(|value| value.to_str().map_err(|_| value))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|value| {
+                    BodyReaderError::Decode(
+                        super::decompress::DecompressError::UnsupportedEncoding {
+                            encoding: String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                        },
+                    )
+                })?
+                .join(",")
+        };
+
+        let mut decoder =
+            super::decompress::Decoder::new(&encoding, limit).map_err(BodyReaderError::Decode)?;
+        let mut chunks = std::pin::pin!(self.body_reader().into_stream());
+        let mut len = 0_usize;
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk.map_err(BodyReaderError::Read)?;
+            len = len.saturating_add(chunk.len());
+            if len > limit {
+                return Err(BodyReaderError::TooLarge { limit });
+            }
+            decoder.write(&chunk).map_err(BodyReaderError::Decode)?;
+        }
+        decoder.finish().map_err(BodyReaderError::Decode)
+    }
+
+    #[cfg(feature = "charset")]
+    async fn text(
+        self,
+    ) -> Result<
+        String,
+        super::body_reader::BodyReaderError<T::Error, super::body_reader::UnknownCharsetError>,
+    >
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf,
+    {
+        let content_type = self.headers().get(http::header::CONTENT_TYPE).cloned();
+        self.body_reader().text(content_type.as_ref()).await
+    }
+
+    #[cfg(feature = "json")]
+    async fn into_api_result<R, E>(self) -> Result<R, ApiError<E, T::Error>>
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf,
+        R: serde::de::DeserializeOwned,
+        E: serde::de::DeserializeOwned,
+    {
+        let status = self.status();
+        if let Some(content_type) = self.headers().get(http::header::CONTENT_TYPE) {
+            if !crate::mime::is_json(content_type) {
+                return Err(ApiError::ContentType {
+                    status,
+                    content_type: content_type.clone(),
+                });
+            }
+        }
+
+        let reader = self.body_reader();
+        let body_error = |source| ApiError::Body { status, source };
+        if status.is_success() {
+            reader.json().await.map_err(body_error)
+        } else {
+            let body = reader.json().await.map_err(body_error)?;
+            Err(ApiError::Status { status, body })
+        }
+    }
+
+    #[cfg(feature = "json")]
+    async fn json_optional<R>(
+        self,
+    ) -> Result<Option<R>, super::body_reader::BodyReaderError<T::Error, serde_json::Error>>
+    where
+        T: http_body::Body,
+        T::Data: bytes::Buf,
+        R: serde::de::DeserializeOwned,
+    {
+        use super::body_reader::BodyReaderError;
+
+        let status = self.status();
+        let bytes = self.bytes().await.map_err(BodyReaderError::Read)?;
+        if matches!(
+            status,
+            http::StatusCode::NO_CONTENT | http::StatusCode::RESET_CONTENT
+        ) || bytes.is_empty()
+        {
+            return Ok(None);
+        }
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(BodyReaderError::Decode)
+    }
 }
@@ -1,9 +1,66 @@
 use std::future::Future;
 
-use http::{Method, Uri};
+use bytes::Bytes;
+use http::{header::CONTENT_LENGTH, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri};
+use http_body::Body;
+use thiserror::Error;
 use tower_service::Service;
 
-use super::{ClientRequest, IntoUri};
+use super::{body_reader::BodyReaderError, BodyReader, ClientRequest, IntoUri};
+
+/// The `Accept` header value of the [`ServiceExt::execute_negotiated`] requests.
+#[cfg(all(feature = "json", feature = "xml"))]
+const NEGOTIATED_ACCEPT: &str = "application/json, application/xml;q=0.9";
+
+/// Errors that can occur while executing a request and reading the full response body.
+#[derive(Debug, Error)]
+pub enum ExecuteBytesError<E, B> {
+    /// An error occurred in the underlying service.
+    #[error(transparent)]
+    Service(E),
+    /// An error occurred while reading the response body.
+    #[error(transparent)]
+    Read(B),
+    /// The response body is shorter than the `Content-Length` header promises.
+    #[error("response body is truncated: expected {expected} bytes, got {actual}")]
+    Truncated {
+        /// The body length declared by the `Content-Length` header.
+        expected: u64,
+        /// The number of bytes actually received.
+        actual: u64,
+    },
+    /// The response body is larger than the limit.
+    #[error("response body is larger than the limit of {limit} bytes")]
+    TooLarge {
+        /// The maximum allowed body length in bytes.
+        limit: usize,
+    },
+}
+
+/// Errors that can occur while executing a request with the content negotiation.
+#[cfg(all(feature = "json", feature = "xml"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "json", feature = "xml"))))]
+#[derive(Debug, Error)]
+pub enum NegotiateError<E, B> {
+    /// An error occurred in the underlying service.
+    #[error(transparent)]
+    Service(E),
+    /// An error occurred while reading the response body.
+    #[error(transparent)]
+    Read(B),
+    /// The response content type is neither JSON nor XML.
+    #[error("unsupported response content type {content_type:?}")]
+    UnsupportedContentType {
+        /// The response `Content-Type` header value, if present.
+        content_type: Option<HeaderValue>,
+    },
+    /// An error occurred while decoding the JSON response body.
+    #[error(transparent)]
+    Json(serde_json::Error),
+    /// An error occurred while decoding the XML response body.
+    #[error(transparent)]
+    Xml(quick_xml::DeError),
+}
 
 /// An extension trait for Tower HTTP services with the typical client methods.
 ///
@@ -18,6 +75,9 @@ use super::{ClientRequest, IntoUri};
 /// [`reqwest::Client`]: https://docs.rs/reqwest/latest/reqwest/struct.Client.html
 pub trait ServiceExt<ReqBody, RespBody, Err>: Sized {
     /// Executes an HTTP request.
+    ///
+    /// The `NoDecompress` extension of the request (`decompression` feature) is copied into
+    /// the response.
     fn execute<R>(
         &mut self,
         request: http::Request<R>,
@@ -25,7 +85,233 @@ pub trait ServiceExt<ReqBody, RespBody, Err>: Sized {
     where
         ReqBody: From<R>;
 
+    /// Consumes this service, waits until it is ready and then executes an HTTP request.
+    ///
+    /// Unlike the [`execute`](Self::execute) method, the returned future doesn't borrow
+    /// the service, so it can be easily spawned as a separate task.
+    ///
+    /// # Note
+    ///
+    /// To keep using the service after calling this method, you have to clone it beforehand.
+    /// The cost of cloning depends on the service: the most of clients like [`reqwest::Client`]
+    /// and the [`tower::buffer::Buffer`] middleware are reference counted and cheap to clone,
+    /// but some other middlewares may copy the whole state.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use tower_http_client::ServiceExt as _;
+    /// use tower_reqwest::HttpClientService;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let client = HttpClientService::new(reqwest::Client::new());
+    ///
+    ///     let request = http::Request::get("http://ip.jsontest.com").body(reqwest::Body::default())?;
+    ///     let handle = tokio::spawn(client.clone().ready_oneshot(request));
+    ///     let response = handle.await??;
+    ///     println!("{}", response.status());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`reqwest::Client`]: https://docs.rs/reqwest/latest/reqwest/struct.Client.html
+    /// [`tower::buffer::Buffer`]: https://docs.rs/tower/latest/tower/buffer/struct.Buffer.html
+    fn ready_oneshot<R>(
+        self,
+        request: http::Request<R>,
+    ) -> impl Future<Output = Result<http::Response<RespBody>, Err>> + Send + 'static
+    where
+        Self: Send + 'static,
+        ReqBody: From<R> + Send + 'static,
+        RespBody: 'static,
+        R: 'static;
+
+    /// Executes an HTTP request and returns the response status, headers and full body.
+    ///
+    /// The body is read into memory entirely, so its length is limited by the `limit`
+    /// bytes. The larger bodies fail with the [`ExecuteBytesError::TooLarge`] error as
+    /// soon as the read data exceeds the limit.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use tower_http_client::ServiceExt as _;
+    /// use tower_reqwest::HttpClientService;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let mut client = HttpClientService::new(reqwest::Client::new());
+    ///
+    ///     let request = http::Request::get("http://ip.jsontest.com").body(reqwest::Body::default())?;
+    ///     let (status, _headers, body) = client.execute_bytes(request, 64 * 1024).await?;
+    ///     println!("{status}: {} bytes", body.len());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn execute_bytes<R>(
+        &mut self,
+        request: http::Request<R>,
+        limit: usize,
+    ) -> impl Future<
+        Output = Result<(StatusCode, HeaderMap, Bytes), ExecuteBytesError<Err, RespBody::Error>>,
+    >
+    where
+        ReqBody: From<R>,
+        RespBody: Body,
+    {
+        async move {
+            let response = self
+                .execute(request)
+                .await
+                .map_err(ExecuteBytesError::Service)?;
+            let (parts, body) = response.into_parts();
+            let bytes =
+                BodyReader::new(body)
+                    .limited(limit)
+                    .bytes()
+                    .await
+                    .map_err(|err| match err {
+                        BodyReaderError::Read(err) => ExecuteBytesError::Read(err),
+                        BodyReaderError::TooLarge { limit } => {
+                            ExecuteBytesError::TooLarge { limit }
+                        }
+                        BodyReaderError::Decode(never) => match never {},
+                    })?;
+            Ok((parts.status, parts.headers, bytes))
+        }
+    }
+
+    /// Executes an HTTP request and reads the full response body into [`Bytes`].
+    ///
+    /// If reading the body fails or the received body is shorter than the `Content-Length`
+    /// response header declares, the whole request is sent once again from scratch.
+    /// That's why the request body has to be [`Clone`]. Errors returned by the service
+    /// itself are not retried.
+    ///
+    /// This method is intended for small and medium sized responses which are read into
+    /// memory entirely, where a silently truncated body is unacceptable.
+    fn execute_bytes_with_retry<R>(
+        &mut self,
+        request: http::Request<R>,
+    ) -> impl Future<Output = Result<http::Response<Bytes>, ExecuteBytesError<Err, RespBody::Error>>>
+    where
+        ReqBody: From<R>,
+        R: Clone,
+        RespBody: Body,
+    {
+        async move {
+            let has_body = request.method() != Method::HEAD;
+            let retry_request = request.clone();
+
+            let response = self
+                .execute(request)
+                .await
+                .map_err(ExecuteBytesError::Service)?;
+            match read_checked_response(response, has_body).await {
+                Err(ExecuteBytesError::Read(_) | ExecuteBytesError::Truncated { .. }) => {
+                    let response = self
+                        .execute(retry_request)
+                        .await
+                        .map_err(ExecuteBytesError::Service)?;
+                    read_checked_response(response, has_body).await
+                }
+                other => other,
+            }
+        }
+    }
+
+    /// Executes an HTTP request accepting either JSON or XML, and decodes the response body
+    /// according to its `Content-Type` header.
+    ///
+    /// The request `Accept` header is set to `application/json, application/xml;q=0.9`,
+    /// so the server picks the representation, preferably JSON. The response status is not
+    /// checked, and the response with any other content type is rejected with the
+    /// [`NegotiateError::UnsupportedContentType`] error without reading its body.
+    ///
+    /// The XML body is deserialized by the [`quick_xml::de`] module, see its documentation
+    /// for the mapping rules.
+    ///
+    /// [`quick_xml::de`]: https://docs.rs/quick-xml/latest/quick_xml/de/index.html
+    #[cfg(all(feature = "json", feature = "xml"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "json", feature = "xml"))))]
+    fn execute_negotiated<T, R>(
+        &mut self,
+        mut request: http::Request<R>,
+    ) -> impl Future<Output = Result<http::Response<T>, NegotiateError<Err, RespBody::Error>>>
+    where
+        ReqBody: From<R>,
+        RespBody: Body,
+        T: serde::de::DeserializeOwned,
+    {
+        async move {
+            request.headers_mut().insert(
+                http::header::ACCEPT,
+                HeaderValue::from_static(NEGOTIATED_ACCEPT),
+            );
+            let response = self
+                .execute(request)
+                .await
+                .map_err(NegotiateError::Service)?;
+
+            let (parts, body) = response.into_parts();
+            let media_type = parts
+                .headers
+                .get(http::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(crate::mime::media_type);
+            let is_json = match media_type {
+                Some(media_type) if crate::mime::is_json_media_type(media_type) => true,
+                Some(media_type) if crate::mime::is_xml_media_type(media_type) => false,
+                _ => {
+                    return Err(NegotiateError::UnsupportedContentType {
+                        content_type: parts.headers.get(http::header::CONTENT_TYPE).cloned(),
+                    })
+                }
+            };
+
+            let bytes = BodyReader::new(body)
+                .bytes()
+                .await
+                .map_err(NegotiateError::Read)?;
+            let content = if is_json {
+                serde_json::from_slice(&bytes).map_err(NegotiateError::Json)?
+            } else {
+                quick_xml::de::from_reader(&*bytes).map_err(NegotiateError::Xml)?
+            };
+            Ok(http::Response::from_parts(parts, content))
+        }
+    }
+
     /// Starts building a request with the given method and URI.
+    ///
+    /// The named helpers, like [`get`](Self::get) or [`post`](Self::post), delegate to this
+    /// method, which also accepts the methods without a dedicated helper, including the
+    /// extension ones.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use http::Method;
+    /// use tower_http_client::ServiceExt as _;
+    /// use tower_reqwest::HttpClientService;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let mut client = HttpClientService::new(reqwest::Client::new());
+    ///     let method = Method::from_bytes(b"PROPFIND")?;
+    ///     let response = client
+    ///         .request(method, "http://example.com/files/")
+    ///         .header("depth", "1")
+    ///         .send()?
+    ///         .await?;
+    ///
+    ///     println!("{}", response.status());
+    ///     Ok(())
+    /// }
+    /// ```
     fn request<U>(
         &mut self,
         method: Method,
@@ -51,6 +337,28 @@ pub trait ServiceExt<ReqBody, RespBody, Err>: Sized {
         self.request(Method::GET, uri)
     }
 
+    /// Convenience method to make a `GET` request to a given URL with the given headers.
+    ///
+    /// Invalid headers are reported by the [`ClientRequest::build`] method.
+    fn get_with<U, I, K, V>(
+        &mut self,
+        uri: U,
+        headers: I,
+    ) -> ClientRequest<'_, Self, Err, ReqBody, RespBody>
+    where
+        ReqBody: Default,
+        U: IntoUri,
+        Uri: TryFrom<U::TryInto>,
+        <Uri as TryFrom<U::TryInto>>::Error: Into<http::Error>,
+        I: IntoIterator<Item = (K, V)>,
+        HeaderName: TryFrom<K>,
+        HeaderValue: TryFrom<V>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.get(uri).headers(headers)
+    }
+
     /// Convenience method to make a `PUT` request to a given URL.
     fn put<U>(&mut self, uri: U) -> ClientRequest<'_, Self, Err, ReqBody, RespBody>
     where
@@ -73,6 +381,28 @@ pub trait ServiceExt<ReqBody, RespBody, Err>: Sized {
         self.request(Method::POST, uri)
     }
 
+    /// Convenience method to make a `POST` request to a given URL with the given headers.
+    ///
+    /// Invalid headers are reported by the [`ClientRequest::build`] method.
+    fn post_with<U, I, K, V>(
+        &mut self,
+        uri: U,
+        headers: I,
+    ) -> ClientRequest<'_, Self, Err, ReqBody, RespBody>
+    where
+        ReqBody: Default,
+        U: IntoUri,
+        Uri: TryFrom<U::TryInto>,
+        <Uri as TryFrom<U::TryInto>>::Error: Into<http::Error>,
+        I: IntoIterator<Item = (K, V)>,
+        HeaderName: TryFrom<K>,
+        HeaderValue: TryFrom<V>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.post(uri).headers(headers)
+    }
+
     /// Convenience method to make a `PATCH` request to a given URL.
     fn patch<U>(&mut self, uri: U) -> ClientRequest<'_, Self, Err, ReqBody, RespBody>
     where
@@ -120,9 +450,65 @@ where
     where
         ReqBody: From<R>,
     {
+        #[cfg(feature = "decompression")]
+        let no_decompress = request.extensions().get::<super::NoDecompress>().copied();
         // Wait until service will be ready to executing requests. It's important for buffered services.
         futures_util::future::poll_fn(|ctx| self.poll_ready(ctx)).await?;
         // And then execute the given request.
-        self.call(request.map(ReqBody::from)).await
+        #[cfg_attr(not(feature = "decompression"), allow(unused_mut))]
+        let mut response = self.call(request.map(ReqBody::from)).await?;
+        // Pass the request intent to the `ResponseExt::decompressed` method.
+        #[cfg(feature = "decompression")]
+        if let Some(no_decompress) = no_decompress {
+            response.extensions_mut().insert(no_decompress);
+        }
+        Ok(response)
+    }
+
+    fn ready_oneshot<R>(
+        self,
+        request: http::Request<R>,
+    ) -> impl Future<Output = Result<http::Response<RespBody>, Err>> + Send + 'static
+    where
+        Self: Send + 'static,
+        ReqBody: From<R> + Send + 'static,
+        RespBody: 'static,
+        R: 'static,
+    {
+        tower::ServiceExt::oneshot(self, request.map(ReqBody::from))
+    }
+}
+
+/// Reads the whole response body and checks it against the `Content-Length` header.
+async fn read_checked_response<B, E>(
+    response: http::Response<B>,
+    has_body: bool,
+) -> Result<http::Response<Bytes>, ExecuteBytesError<E, B::Error>>
+where
+    B: Body,
+{
+    let (parts, body) = response.into_parts();
+    let expected = parts
+        .headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|_| {
+            has_body
+                && !parts.status.is_informational()
+                && parts.status != StatusCode::NO_CONTENT
+                && parts.status != StatusCode::NOT_MODIFIED
+        });
+
+    let bytes = BodyReader::new(body)
+        .bytes()
+        .await
+        .map_err(ExecuteBytesError::Read)?;
+    if let Some(expected) = expected {
+        let actual = bytes.len() as u64;
+        if actual < expected {
+            return Err(ExecuteBytesError::Truncated { expected, actual });
+        }
     }
+    Ok(http::Response::from_parts(parts, bytes))
 }
@@ -0,0 +1,157 @@
+//! A request body wrapper that enforces the declared length.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Buf;
+use http_body::{Body, Frame, SizeHint};
+use pin_project::pin_project;
+use thiserror::Error;
+
+/// A [`Body`] wrapper that enforces the declared length of the inner body.
+///
+/// Misbehaving sources that yield more or fewer bytes than declared fail with
+/// an error instead of sending a corrupted request.
+#[pin_project]
+#[derive(Debug, Clone)]
+pub struct SizedBody<B> {
+    #[pin]
+    inner: B,
+    length: u64,
+    remaining: u64,
+}
+
+/// Errors that can occur while reading the [`SizedBody`].
+#[derive(Debug, Error)]
+pub enum SizedBodyError<E> {
+    /// An error occurred in the inner body.
+    #[error(transparent)]
+    Body(E),
+    /// The inner body yields more bytes than declared.
+    #[error("body is longer than the declared length of {expected} bytes")]
+    TooLong {
+        /// The declared body length.
+        expected: u64,
+    },
+    /// The inner body ends before yielding the declared number of bytes.
+    #[error("body ends after {actual} bytes, but {expected} bytes are declared")]
+    TooShort {
+        /// The declared body length.
+        expected: u64,
+        /// The number of bytes actually yielded.
+        actual: u64,
+    },
+}
+
+impl<B> SizedBody<B> {
+    /// Wraps the given body with the declared length.
+    pub const fn new(inner: B, length: u64) -> Self {
+        Self {
+            inner,
+            length,
+            remaining: length,
+        }
+    }
+
+    /// Returns the declared length of this body.
+    pub const fn length(&self) -> u64 {
+        self.length
+    }
+}
+
+impl<B: Body> Body for SizedBody<B> {
+    type Data = B::Data;
+    type Error = SizedBodyError<B::Error>;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = match std::task::ready!(this.inner.poll_frame(cx)) {
+            Some(Ok(frame)) => frame,
+            Some(Err(err)) => return Poll::Ready(Some(Err(SizedBodyError::Body(err)))),
+            None if *this.remaining > 0 => {
+                return Poll::Ready(Some(Err(SizedBodyError::TooShort {
+                    expected: *this.length,
+                    actual: *this.length - *this.remaining,
+                })));
+            }
+            None => return Poll::Ready(None),
+        };
+
+        if let Some(data) = frame.data_ref() {
+            let len = data.remaining() as u64;
+            if len > *this.remaining {
+                return Poll::Ready(Some(Err(SizedBodyError::TooLong {
+                    expected: *this.length,
+                })));
+            }
+            *this.remaining -= len;
+        }
+        Poll::Ready(Some(Ok(frame)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.remaining == 0 && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.remaining)
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl<B> From<SizedBody<B>> for reqwest::Body
+where
+    B: Body + Send + 'static,
+    B::Error: std::error::Error + Send + Sync + 'static,
+    bytes::Bytes: From<B::Data>,
+{
+    fn from(value: SizedBody<B>) -> Self {
+        reqwest::Body::wrap_stream(http_body_util::BodyDataStream::new(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures_util::stream;
+    use http_body::Frame;
+    use http_body_util::{BodyExt as _, Full, StreamBody};
+
+    use super::{SizedBody, SizedBodyError};
+
+    #[tokio::test]
+    async fn test_sized_body_exact() {
+        let body = SizedBody::new(Full::new(Bytes::from_static(b"Hello")), 5);
+        let bytes = body.collect().await.unwrap().to_bytes();
+        assert_eq!(bytes, "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_sized_body_too_long() {
+        let body = SizedBody::new(Full::new(Bytes::from_static(b"Hello")), 4);
+        let error = body.collect().await.unwrap_err();
+        assert!(matches!(error, SizedBodyError::TooLong { expected: 4 }));
+    }
+
+    #[tokio::test]
+    async fn test_sized_body_too_short() {
+        let chunks = stream::iter([
+            Ok::<_, std::convert::Infallible>(Frame::data(Bytes::from_static(b"Hel"))),
+            Ok(Frame::data(Bytes::from_static(b"lo"))),
+        ]);
+        let body = SizedBody::new(StreamBody::new(chunks), 10);
+        let error = body.collect().await.unwrap_err();
+        assert!(matches!(
+            error,
+            SizedBodyError::TooShort {
+                expected: 10,
+                actual: 5
+            }
+        ));
+    }
+}
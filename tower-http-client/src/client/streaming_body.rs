@@ -0,0 +1,151 @@
+//! A request body fed by a [`Sink`] while the request is in flight.
+//!
+//! [`Sink`]: futures_sink::Sink
+
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_channel::mpsc;
+use futures_sink::Sink;
+use futures_util::Stream as _;
+use http_body::{Body, Frame};
+use thiserror::Error;
+
+/// Creates a new streaming body and the sender which feeds it.
+///
+/// The `buffer` is the number of chunks which can be sent without waiting for the body
+/// to be read.
+#[must_use]
+pub fn channel(buffer: usize) -> (BodySender, StreamingBody) {
+    let (sender, receiver) = mpsc::channel(buffer);
+    let finished = Arc::new(AtomicBool::new(false));
+    (
+        BodySender {
+            sender,
+            finished: finished.clone(),
+        },
+        StreamingBody { receiver, finished },
+    )
+}
+
+/// The sending half of the [`StreamingBody`].
+///
+/// It implements the [`Sink<Bytes>`](Sink) trait, each sent chunk is passed to the request
+/// body. Closing the sink finalizes the body, while dropping it without closing aborts
+/// the body with the [`BodyAborted`] error, so the truncated body is not sent silently.
+#[derive(Debug)]
+pub struct BodySender {
+    sender: mpsc::Sender<Bytes>,
+    finished: Arc<AtomicBool>,
+}
+
+/// Errors that can occur while sending a chunk to the [`BodySender`].
+#[derive(Debug, Error)]
+#[error("request body is no longer read")]
+pub struct BodySendError(#[from] mpsc::SendError);
+
+impl Sink<Bytes> for BodySender {
+    type Error = BodySendError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.sender)
+            .poll_ready(cx)
+            .map_err(From::from)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Bytes) -> Result<(), Self::Error> {
+        Pin::new(&mut self.sender)
+            .start_send(item)
+            .map_err(From::from)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.sender)
+            .poll_flush(cx)
+            .map_err(From::from)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.finished.store(true, Ordering::Release);
+        Pin::new(&mut self.sender)
+            .poll_close(cx)
+            .map_err(From::from)
+    }
+}
+
+/// The [`BodySender`] was dropped without closing.
+#[derive(Debug, Error)]
+#[error("request body sender is dropped without closing")]
+pub struct BodyAborted;
+
+/// A request body which receives its chunks from the [`BodySender`].
+///
+/// This body is created by the [`channel`] function.
+#[derive(Debug)]
+pub struct StreamingBody {
+    receiver: mpsc::Receiver<Bytes>,
+    finished: Arc<AtomicBool>,
+}
+
+impl Body for StreamingBody {
+    type Data = Bytes;
+    type Error = BodyAborted;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        match std::task::ready!(Pin::new(&mut self.receiver).poll_next(cx)) {
+            Some(chunk) => Poll::Ready(Some(Ok(Frame::data(chunk)))),
+            None if self.finished.load(Ordering::Acquire) => Poll::Ready(None),
+            None => Poll::Ready(Some(Err(BodyAborted))),
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl From<StreamingBody> for reqwest::Body {
+    fn from(value: StreamingBody) -> Self {
+        reqwest::Body::wrap_stream(http_body_util::BodyDataStream::new(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures_util::SinkExt as _;
+    use http_body_util::BodyExt as _;
+
+    use super::channel;
+
+    #[tokio::test]
+    async fn test_streaming_body() {
+        let (mut sender, body) = channel(1);
+        let reader = tokio::spawn(body.collect());
+
+        sender.send(Bytes::from_static(b"Hello ")).await.unwrap();
+        sender.send(Bytes::from_static(b"world")).await.unwrap();
+        sender.close().await.unwrap();
+
+        let bytes = reader.await.unwrap().unwrap().to_bytes();
+        assert_eq!(bytes, "Hello world");
+    }
+
+    #[tokio::test]
+    async fn test_streaming_body_aborted() {
+        let (mut sender, body) = channel(1);
+        let reader = tokio::spawn(body.collect());
+
+        sender.send(Bytes::from_static(b"Hello")).await.unwrap();
+        drop(sender);
+
+        assert!(reader.await.unwrap().is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use bytes::{Buf, Bytes};
+
+/// A request body that can be cloned if its content is available in memory.
+///
+/// Middlewares which have to replay requests, like the authentication ones, use this trait to
+/// make a copy of the request body. Unlike the [`Clone`] trait, the cloning may fail at runtime,
+/// for example, if the body is a stream.
+///
+/// The trait is implemented for the in-memory body types from the [`bytes`], [`http_body_util`]
+/// and [`reqwest`] crates.
+///
+/// [`bytes`]: https://docs.rs/bytes
+/// [`http_body_util`]: https://docs.rs/http-body-util
+/// [`reqwest`]: https://docs.rs/reqwest
+pub trait TryCloneBody: Sized {
+    /// Tries to make a copy of this body.
+    ///
+    /// Returns `None` if the body cannot be cloned.
+    fn try_clone_body(&self) -> Option<Self>;
+}
+
+macro_rules! impl_try_clone_body_via_clone {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl TryCloneBody for $ty {
+                fn try_clone_body(&self) -> Option<Self> {
+                    Some(self.clone())
+                }
+            }
+        )*
+    };
+}
+
+impl_try_clone_body_via_clone!((), Bytes, String, Vec<u8>, &'static str, &'static [u8]);
+
+impl<D: Buf + Clone> TryCloneBody for http_body_util::Full<D> {
+    fn try_clone_body(&self) -> Option<Self> {
+        Some(self.clone())
+    }
+}
+
+impl<D> TryCloneBody for http_body_util::Empty<D> {
+    fn try_clone_body(&self) -> Option<Self> {
+        Some(Self::new())
+    }
+}
+
+/// Only the bodies created from the in-memory data can be cloned, the data is copied.
+#[cfg(feature = "reqwest")]
+impl TryCloneBody for reqwest::Body {
+    fn try_clone_body(&self) -> Option<Self> {
+        self.as_bytes()
+            .map(|bytes| Self::from(Bytes::copy_from_slice(bytes)))
+    }
+}
+
+/// Tries to make a copy of the given request including its extensions.
+pub(crate) fn try_clone_request<B: TryCloneBody>(
+    request: &http::Request<B>,
+) -> Option<http::Request<B>> {
+    let body = request.body().try_clone_body()?;
+
+    let mut clone = http::Request::new(body);
+    *clone.method_mut() = request.method().clone();
+    *clone.uri_mut() = request.uri().clone();
+    *clone.version_mut() = request.version();
+    *clone.headers_mut() = request.headers().clone();
+    *clone.extensions_mut() = request.extensions().clone();
+    Some(clone)
+}
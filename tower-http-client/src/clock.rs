@@ -0,0 +1,70 @@
+//! Time source for the middlewares, which wait between requests.
+//!
+//! The `RetryLayer` (`retry` feature) and `ConcurrencyAndRateLayer` (`limit` feature)
+//! middlewares read the current time and wait through the [`Clock`] trait, so a custom
+//! clock allows to skip or record the delays in tests and benchmarks.
+
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use futures_util::future::BoxFuture;
+
+/// Source of the current time and timers.
+///
+/// The default [`TokioClock`] uses the system time and the Tokio timers.
+pub trait Clock: Send + Sync + 'static {
+    /// Returns the current time, which the `Retry-After` dates are resolved against.
+    fn now(&self) -> SystemTime;
+
+    /// Returns the current monotonic time, which the rate intervals are measured with.
+    fn instant(&self) -> Instant;
+
+    /// Returns a future, which completes after the given duration.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// The default [`Clock`] using the system time and the Tokio timers.
+///
+/// The monotonic time is read from the Tokio clock, so it follows the paused time in the
+/// Tokio tests.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn instant(&self) -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+// The clock shared between the middleware clones.
+#[derive(Clone)]
+pub(crate) struct SharedClock(pub(crate) Arc<dyn Clock>);
+
+impl SharedClock {
+    pub(crate) fn new(clock: impl Clock) -> Self {
+        Self(Arc::new(clock))
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        Self::new(TokioClock)
+    }
+}
+
+impl fmt::Debug for SharedClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedClock").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,530 @@
+//! Middleware that keeps cookies between requests.
+//!
+//! The middleware reads the `Set-Cookie` headers of responses, stores the cookies in the
+//! shared [`CookieStore`] and sends the matching ones back in the `Cookie` header of the
+//! subsequent requests. The stored cookies respect the `Domain`, `Path`, `Secure`,
+//! `Max-Age` and `Expires` attributes according to the [RFC 6265]. The `Domain` attribute
+//! cannot be a public suffix, like `com` or `co.uk`, according to the [Public Suffix List].
+//!
+//! The store is shared between the clones of the service, so the cookies set by the one
+//! request are visible to all the others. Use the [`CookieJarLayer::with_store`] method to
+//! pre-seed or inspect the cookies.
+//!
+//! # Example
+//!
+//! ```
+//! use std::sync::{Arc, Mutex};
+//!
+//! use tower::ServiceBuilder;
+//! use tower_http_client::cookie_jar::{CookieJarLayer, CookieStore};
+//! use tower_reqwest::HttpClientLayer;
+//!
+//! let store = Arc::new(Mutex::new(CookieStore::default()));
+//! let client = ServiceBuilder::new()
+//!     .layer(CookieJarLayer::with_store(store.clone()))
+//!     .layer(HttpClientLayer)
+//!     .service(reqwest::Client::new());
+//! ```
+//!
+//! [RFC 6265]: https://datatracker.ietf.org/doc/html/rfc6265
+//! [Public Suffix List]: https://publicsuffix.org
+
+use std::{
+    future::Future,
+    net::IpAddr,
+    pin::Pin,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+
+use http::{
+    header::{COOKIE, SET_COOKIE},
+    HeaderMap, HeaderValue, Request, Response, Uri,
+};
+use pin_project::pin_project;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A storage of the cookies received from the servers.
+#[derive(Debug, Clone, Default)]
+pub struct CookieStore {
+    cookies: Vec<StoredCookie>,
+}
+
+#[derive(Debug, Clone)]
+struct StoredCookie {
+    name: String,
+    value: String,
+    domain: String,
+    host_only: bool,
+    path: String,
+    secure: bool,
+    expires_at: Option<SystemTime>,
+}
+
+impl StoredCookie {
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    fn matches(&self, uri: &Uri, host: &str) -> bool {
+        let domain_matches = if self.host_only {
+            host == self.domain
+        } else {
+            domain_match(host, &self.domain)
+        };
+        domain_matches && path_match(uri.path(), &self.path) && (!self.secure || is_secure(uri))
+    }
+}
+
+impl CookieStore {
+    /// Creates a new empty store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores the cookie from the given `Set-Cookie` header value received from the `uri`.
+    ///
+    /// Returns `false` if the cookie is malformed or is not allowed to be set by the `uri`,
+    /// for example, if its `Domain` attribute doesn't match the request host.
+    pub fn insert(&mut self, set_cookie: &str, uri: &Uri) -> bool {
+        let Some(host) = host(uri) else {
+            return false;
+        };
+        let Some(cookie) = parse_set_cookie(set_cookie, uri, &host, SystemTime::now()) else {
+            return false;
+        };
+
+        self.cookies.retain(|stored| {
+            stored.name != cookie.name
+                || stored.domain != cookie.domain
+                || stored.path != cookie.path
+        });
+        // The expired cookie only removes the stored one.
+        if !cookie.is_expired(SystemTime::now()) {
+            self.cookies.push(cookie);
+        }
+        true
+    }
+
+    /// Stores the cookies from the `Set-Cookie` headers of the response received from the `uri`.
+    pub fn store_response_cookies(&mut self, headers: &HeaderMap, uri: &Uri) {
+        for value in headers.get_all(SET_COOKIE) {
+            if let Ok(value) = value.to_str() {
+                self.insert(value, uri);
+            }
+        }
+    }
+
+    /// Returns the value of the named cookie which would be sent to the `uri`.
+    #[must_use]
+    pub fn get(&self, name: &str, uri: &Uri) -> Option<&str> {
+        self.matching(uri)
+            .into_iter()
+            .find(|cookie| cookie.name == name)
+            .map(|cookie| cookie.value.as_str())
+    }
+
+    /// Returns the `Cookie` header value with all the cookies which should be sent
+    /// to the `uri`.
+    ///
+    /// The cookies with longer paths are listed first.
+    #[must_use]
+    pub fn cookie_header(&self, uri: &Uri) -> Option<HeaderValue> {
+        let mut cookies = self.matching(uri);
+        if cookies.is_empty() {
+            return None;
+        }
+        cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+
+        let value = cookies
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        HeaderValue::try_from(value).ok()
+    }
+
+    /// Removes the expired cookies from the store.
+    pub fn remove_expired(&mut self) {
+        let now = SystemTime::now();
+        self.cookies.retain(|cookie| !cookie.is_expired(now));
+    }
+
+    /// Removes all the cookies from the store.
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+
+    /// Returns the number of the stored cookies, including the expired ones.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    /// Returns `true` if the store contains no cookies.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    fn matching(&self, uri: &Uri) -> Vec<&StoredCookie> {
+        let Some(host) = host(uri) else {
+            return Vec::new();
+        };
+        let now = SystemTime::now();
+        self.cookies
+            .iter()
+            .filter(|cookie| !cookie.is_expired(now) && cookie.matches(uri, &host))
+            .collect()
+    }
+}
+
+/// Layer that applies the [`CookieJar`] middleware.
+#[derive(Debug, Clone, Default)]
+pub struct CookieJarLayer {
+    store: Arc<Mutex<CookieStore>>,
+}
+
+impl CookieJarLayer {
+    /// Creates a new layer with an empty cookie store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new layer with the given cookie store.
+    #[must_use]
+    pub fn with_store(store: Arc<Mutex<CookieStore>>) -> Self {
+        Self { store }
+    }
+
+    /// Returns the cookie store used by this layer.
+    #[must_use]
+    pub fn store(&self) -> Arc<Mutex<CookieStore>> {
+        self.store.clone()
+    }
+}
+
+impl<S> Layer<S> for CookieJarLayer {
+    type Service = CookieJar<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CookieJar {
+            inner,
+            store: self.store.clone(),
+        }
+    }
+}
+
+/// Middleware that keeps cookies between requests.
+///
+/// The `Cookie` header set on the request explicitly is kept, and the stored cookies are
+/// appended to it.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct CookieJar<S> {
+    inner: S,
+    store: Arc<Mutex<CookieStore>>,
+}
+
+impl<S> CookieJar<S> {
+    /// Creates a new middleware with an empty cookie store.
+    pub fn new(inner: S) -> Self {
+        CookieJarLayer::new().layer(inner)
+    }
+
+    /// Returns the cookie store used by this middleware.
+    pub fn store(&self) -> Arc<Mutex<CookieStore>> {
+        self.store.clone()
+    }
+}
+
+impl<S, ReqBody, RespBody> Service<Request<ReqBody>> for CookieJar<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<RespBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let cookies = self
+            .store
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .cookie_header(req.uri());
+        if let Some(cookies) = cookies {
+            let value = match req.headers().get(COOKIE) {
+                Some(existing) => {
+                    let mut value = existing.as_bytes().to_vec();
+                    value.extend_from_slice(b"; ");
+                    value.extend_from_slice(cookies.as_bytes());
+                    HeaderValue::from_bytes(&value).unwrap_or(cookies)
+                }
+                None => cookies,
+            };
+            req.headers_mut().insert(COOKIE, value);
+        }
+
+        ResponseFuture {
+            uri: req.uri().clone(),
+            inner: self.inner.call(req),
+            store: self.store.clone(),
+        }
+    }
+}
+
+/// Response future for the [`CookieJar`] middleware.
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    uri: Uri,
+    store: Arc<Mutex<CookieStore>>,
+}
+
+impl<F, RespBody, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<RespBody>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = std::task::ready!(this.inner.poll(cx));
+
+        if let Ok(response) = &result {
+            this.store
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .store_response_cookies(response.headers(), this.uri);
+        }
+        Poll::Ready(result)
+    }
+}
+
+// Returns the lowercase request host, if any.
+fn host(uri: &Uri) -> Option<String> {
+    uri.host()
+        .filter(|host| !host.is_empty())
+        .map(str::to_ascii_lowercase)
+}
+
+fn is_secure(uri: &Uri) -> bool {
+    uri.scheme_str().is_some_and(|scheme| {
+        scheme.eq_ignore_ascii_case("https") || scheme.eq_ignore_ascii_case("wss")
+    })
+}
+
+// See https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.3
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+            && host.parse::<IpAddr>().is_err())
+}
+
+// Returns `true` for the public suffixes and the single-label domains.
+fn is_public_suffix(domain: &str) -> bool {
+    !domain.contains('.') || psl::suffix_str(domain) == Some(domain)
+}
+
+// See https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.4
+fn path_match(path: &str, cookie_path: &str) -> bool {
+    let path = if path.is_empty() { "/" } else { path };
+    path == cookie_path
+        || (path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || path.as_bytes()[cookie_path.len()] == b'/'))
+}
+
+fn default_path(uri: &Uri) -> String {
+    match uri.path().rfind('/') {
+        Some(0) | None => "/".to_owned(),
+        Some(index) => uri.path()[..index].to_owned(),
+    }
+}
+
+// See https://datatracker.ietf.org/doc/html/rfc6265#section-5.2
+fn parse_set_cookie(value: &str, uri: &Uri, host: &str, now: SystemTime) -> Option<StoredCookie> {
+    let mut attributes = value.split(';');
+    let (name, value) = attributes.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut cookie = StoredCookie {
+        name: name.to_owned(),
+        value: value.trim().to_owned(),
+        domain: host.to_owned(),
+        host_only: true,
+        path: default_path(uri),
+        secure: false,
+        expires_at: None,
+    };
+    let mut max_age = None;
+    for attribute in attributes {
+        let (key, value) = attribute
+            .split_once('=')
+            .map_or((attribute, ""), |(key, value)| (key, value));
+        let (key, value) = (key.trim(), value.trim());
+
+        if key.eq_ignore_ascii_case("domain") {
+            let domain = value.trim_start_matches('.').to_ascii_lowercase();
+            if !domain.is_empty() {
+                if !domain_match(host, &domain) {
+                    return None;
+                }
+                // The public suffixes, like `com` or `co.uk`, cannot be shared between
+                // the hosts, see https://datatracker.ietf.org/doc/html/rfc6265#section-5.3
+                if is_public_suffix(&domain) {
+                    if domain != host {
+                        return None;
+                    }
+                    continue;
+                }
+                cookie.domain = domain;
+                cookie.host_only = false;
+            }
+        } else if key.eq_ignore_ascii_case("path") {
+            if value.starts_with('/') {
+                value.clone_into(&mut cookie.path);
+            }
+        } else if key.eq_ignore_ascii_case("secure") {
+            cookie.secure = true;
+        } else if key.eq_ignore_ascii_case("max-age") {
+            if let Ok(seconds) = value.parse::<i64>() {
+                max_age = Some(seconds);
+            }
+        } else if key.eq_ignore_ascii_case("expires") {
+            if let Ok(expires_at) = httpdate::parse_http_date(value) {
+                cookie.expires_at = Some(expires_at);
+            }
+        }
+    }
+    // The `Max-Age` attribute has precedence over the `Expires` one.
+    if let Some(seconds) = max_age {
+        cookie.expires_at = match u64::try_from(seconds) {
+            // The expiry time beyond the system time range is treated as no expiry.
+            Ok(seconds) if seconds > 0 => now.checked_add(Duration::from_secs(seconds)),
+            _ => Some(SystemTime::UNIX_EPOCH),
+        };
+    }
+    Some(cookie)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use http::{
+        header::{COOKIE, SET_COOKIE},
+        Request, Response, Uri,
+    };
+    use tower::{service_fn, ServiceExt as _};
+
+    use super::{CookieJar, CookieStore};
+
+    #[test]
+    fn test_cookie_store_attributes() {
+        let login = Uri::from_static("https://www.example.com/account/login");
+        let mut store = CookieStore::new();
+
+        assert!(store.insert("session=abc; Path=/; Secure; HttpOnly", &login));
+        assert!(store.insert("shared=1; Domain=.example.com; Path=/", &login));
+        assert!(store.insert("local=2", &login));
+        assert!(!store.insert("foreign=3; Domain=other.com", &login));
+        assert!(!store.insert("x=1; Domain=com", &login));
+        assert!(!store.insert(
+            "x=1; Domain=co.uk",
+            &Uri::from_static("https://example.co.uk/")
+        ));
+        // The public suffix domain equal to the host is treated as the host-only one.
+        let localhost = Uri::from_static("http://localhost/");
+        assert!(store.insert("x=1; Domain=localhost", &localhost));
+        assert_eq!(store.get("x", &localhost), Some("1"));
+        assert!(!store.insert("malformed", &login));
+
+        let uri = Uri::from_static("https://www.example.com/account/settings");
+        assert_eq!(store.get("session", &uri), Some("abc"));
+        assert_eq!(store.get("local", &uri), Some("2"));
+        assert_eq!(
+            store.cookie_header(&uri).unwrap(),
+            "local=2; session=abc; shared=1"
+        );
+
+        // Secure cookies are not sent over an insecure connection.
+        let uri = Uri::from_static("http://www.example.com/account");
+        assert_eq!(store.cookie_header(&uri).unwrap(), "local=2; shared=1");
+        // Host-only cookies are not sent to the subdomains.
+        let uri = Uri::from_static("https://api.example.com/");
+        assert_eq!(store.cookie_header(&uri).unwrap(), "shared=1");
+        // Path-scoped cookies are not sent outside of their path.
+        let uri = Uri::from_static("https://www.example.com/accounts");
+        assert_eq!(store.get("local", &uri), None);
+        // Cookies are not sent to the other domains.
+        let uri = Uri::from_static("https://example.org/");
+        assert!(store.cookie_header(&uri).is_none());
+    }
+
+    #[test]
+    fn test_cookie_store_expiry() {
+        let uri = Uri::from_static("http://localhost/");
+        let mut store = CookieStore::new();
+
+        store.insert("a=1", &uri);
+        store.insert("b=2; Max-Age=3600", &uri);
+        store.insert("c=3; Expires=Wed, 21 Oct 2015 07:28:00 GMT", &uri);
+        store.insert("d=4; Max-Age=9223372036854775807", &uri);
+        assert_eq!(store.cookie_header(&uri).unwrap(), "a=1; b=2; d=4");
+
+        // The expired cookie replaces the stored one.
+        store.insert("a=1; Max-Age=0", &uri);
+        store.insert("d=4; Max-Age=-1", &uri);
+        assert_eq!(store.cookie_header(&uri).unwrap(), "b=2");
+        assert_eq!(store.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cookie_jar() {
+        let service = CookieJar::new(service_fn(|request: Request<()>| async move {
+            let cookies = request
+                .headers()
+                .get(COOKIE)
+                .map(|value| value.to_str().unwrap().to_owned());
+            let response = Response::builder()
+                .header(SET_COOKIE, "session=abc")
+                .body(cookies)
+                .unwrap();
+            Ok::<_, Infallible>(response)
+        }));
+
+        let request = Request::get("http://localhost/").body(()).unwrap();
+        let response = service.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.into_body(), None);
+
+        // The clones share the same cookie store.
+        let request = Request::get("http://localhost/")
+            .header(COOKIE, "explicit=1")
+            .body(())
+            .unwrap();
+        let response = service.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.into_body().unwrap(), "explicit=1; session=abc");
+
+        let store = service.store();
+        let store = store.lock().unwrap();
+        assert_eq!(
+            store.get("session", &Uri::from_static("http://localhost/")),
+            Some("abc")
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! Middleware that suppresses duplicate requests within a time window.
+//!
+//! Side-effecting requests, like `POST` ones, are sometimes accidentally sent twice, for
+//! example, on a double click. If such requests carry the same idempotency key, this
+//! middleware sends only the first one and returns its response to all duplicates received
+//! within the configured TTL, including the ones arriving while the first request is still
+//! in flight.
+//!
+//! Unlike the singleflight approach, the response is kept for the whole TTL after the
+//! first request, so the later duplicates don't reach the server either.
+//!
+//! # Buffering
+//!
+//! To share the response between the duplicates, the response body is buffered in memory.
+//! Responses with bodies larger than the [`max_body_size`] fail with the [`ResponseTooLarge`]
+//! error. Failed requests are not cached, so a duplicate sent after the failure reaches
+//! the server again.
+//!
+//! The other errors of the first request are shared with the duplicates as the
+//! [`SharedError`], the original error can be inspected by its [`inner`](SharedError::inner)
+//! method.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use tower::ServiceBuilder;
+//! use tower_http_client::dedup_window::DedupWindowLayer;
+//! use tower_reqwest::HttpClientLayer;
+//!
+//! let client = ServiceBuilder::new()
+//!     .layer(DedupWindowLayer::new(Duration::from_secs(10)).max_body_size(64 * 1024))
+//!     .layer(HttpClientLayer)
+//!     .service(reqwest::Client::new());
+//! ```
+//!
+//! [`max_body_size`]: DedupWindowLayer::max_body_size
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use futures_util::{
+    future::{BoxFuture, Shared},
+    FutureExt as _,
+};
+use http::{HeaderMap, HeaderName, HeaderValue, StatusCode, Version};
+use http_body::Body;
+use http_body_util::{BodyExt as _, LengthLimitError, Limited};
+use thiserror::Error;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::BoxError;
+
+/// The default name of the header carrying the idempotency key.
+const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// The default maximum size of the buffered response body.
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// The response body exceeds the buffering limit.
+#[derive(Debug, Clone, Copy, Error)]
+#[error("response body is larger than the limit of {limit} bytes")]
+pub struct ResponseTooLarge {
+    /// The maximum allowed body size in bytes.
+    pub limit: usize,
+}
+
+/// Layer that applies the [`DedupWindow`] middleware.
+#[derive(Debug, Clone)]
+pub struct DedupWindowLayer {
+    ttl: Duration,
+    header: HeaderName,
+    max_body_size: usize,
+}
+
+impl DedupWindowLayer {
+    /// Creates a new layer which keeps the responses for the given TTL.
+    ///
+    /// By default, the idempotency key is read from the `Idempotency-Key` header and
+    /// the responses up to 1 MiB are buffered.
+    #[must_use]
+    pub const fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            header: IDEMPOTENCY_KEY,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    /// Sets the name of the header carrying the idempotency key.
+    #[must_use]
+    pub fn header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Sets the maximum size of the buffered response body in bytes.
+    #[must_use]
+    pub const fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+}
+
+impl<S> Layer<S> for DedupWindowLayer {
+    type Service = DedupWindow<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DedupWindow {
+            inner,
+            config: Arc::new(self.clone()),
+            cache: Arc::default(),
+        }
+    }
+}
+
+/// Middleware that suppresses duplicate requests carrying the same idempotency key.
+///
+/// Requests without the idempotency key are passed through as is. The cache is shared
+/// between the clones of the service.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct DedupWindow<S> {
+    inner: S,
+    config: Arc<DedupWindowLayer>,
+    cache: Arc<Mutex<HashMap<HeaderValue, Entry>>>,
+}
+
+impl<S> DedupWindow<S> {
+    /// Creates a new middleware which keeps the responses for the given TTL.
+    pub fn new(inner: S, ttl: Duration) -> Self {
+        DedupWindowLayer::new(ttl).layer(inner)
+    }
+}
+
+type SharedResponse = Shared<BoxFuture<'static, Result<CachedResponse, SharedError>>>;
+
+#[derive(Debug)]
+struct Entry {
+    expires_at: Instant,
+    response: SharedResponse,
+}
+
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl CachedResponse {
+    fn to_response<B: From<Bytes>>(&self) -> http::Response<B> {
+        let mut response = http::Response::new(B::from(self.body.clone()));
+        *response.status_mut() = self.status;
+        *response.version_mut() = self.version;
+        *response.headers_mut() = self.headers.clone();
+        response
+    }
+}
+
+/// The error of the first request shared between all its duplicates.
+#[derive(Debug, Clone)]
+pub struct SharedError(Arc<BoxError>);
+
+impl SharedError {
+    /// Returns the original error, which can be downcast to the concrete type.
+    #[must_use]
+    pub fn inner(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        &**self.0
+    }
+}
+
+impl fmt::Display for SharedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for SharedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&**self.0)
+    }
+}
+
+impl<S, ReqBody, RespBody> Service<http::Request<ReqBody>> for DedupWindow<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<RespBody>>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+    ReqBody: Send + 'static,
+    RespBody: Body + From<Bytes> + Send + 'static,
+    RespBody::Data: Send,
+    RespBody::Error: Into<BoxError>,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        // Take the service that was ready.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let Some(key) = request.headers().get(&self.config.header).cloned() else {
+            return Box::pin(async move { inner.call(request).await.map_err(Into::into) });
+        };
+
+        let response = {
+            let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+            let now = Instant::now();
+            cache.retain(|_, entry| entry.expires_at > now);
+
+            cache
+                .entry(key.clone())
+                .or_insert_with(|| {
+                    let max_body_size = self.config.max_body_size;
+                    let future = async move {
+                        let response = inner.call(request).await.map_err(Into::into)?;
+                        buffer_response(response, max_body_size).await
+                    };
+                    Entry {
+                        expires_at: now + self.config.ttl,
+                        response: future
+                            .map(|result| result.map_err(|err| SharedError(Arc::new(err))))
+                            .boxed()
+                            .shared(),
+                    }
+                })
+                .response
+                .clone()
+        };
+
+        let cache = self.cache.clone();
+        Box::pin(async move {
+            match response.clone().await {
+                Ok(cached) => Ok(cached.to_response()),
+                Err(err) => {
+                    // Don't keep failed requests, so they can be retried.
+                    let mut cache = cache.lock().unwrap_or_else(PoisonError::into_inner);
+                    if cache
+                        .get(&key)
+                        .is_some_and(|entry| entry.response.ptr_eq(&response))
+                    {
+                        cache.remove(&key);
+                    }
+                    // The known errors are returned as is, so they can be downcast directly.
+                    match err.inner().downcast_ref::<ResponseTooLarge>() {
+                        Some(too_large) => Err((*too_large).into()),
+                        None => Err(err.into()),
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Reads the whole response into memory, if its body doesn't exceed the limit.
+async fn buffer_response<B>(
+    response: http::Response<B>,
+    limit: usize,
+) -> Result<CachedResponse, BoxError>
+where
+    B: Body,
+    B::Error: Into<BoxError>,
+{
+    let (parts, body) = response.into_parts();
+    let body = match Limited::new(body, limit).collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(err) if err.is::<LengthLimitError>() => return Err(ResponseTooLarge { limit }.into()),
+        Err(err) => return Err(err),
+    };
+
+    Ok(CachedResponse {
+        status: parts.status,
+        version: parts.version,
+        headers: parts.headers,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use bytes::Bytes;
+    use http_body_util::{BodyExt as _, Full};
+    use tower::{service_fn, ServiceExt as _};
+    use tower_layer::Layer as _;
+
+    use super::{DedupWindowLayer, ResponseTooLarge, SharedError};
+
+    fn request(key: Option<&'static str>) -> http::Request<()> {
+        let mut builder = http::Request::builder().method("POST");
+        if let Some(key) = key {
+            builder = builder.header("idempotency-key", key);
+        }
+        builder.body(()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_dedup_window() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service = DedupWindowLayer::new(Duration::from_secs(60)).layer(service_fn({
+            let calls = calls.clone();
+            move |_request: http::Request<()>| {
+                let calls = calls.clone();
+                async move {
+                    let n = calls.fetch_add(1, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                    Ok::<_, std::convert::Infallible>(http::Response::new(Full::new(Bytes::from(
+                        format!("response {n}"),
+                    ))))
+                }
+            }
+        }));
+
+        // Concurrent and subsequent duplicates share the first response.
+        let (first, second) = tokio::join!(
+            service.clone().oneshot(request(Some("a"))),
+            service.clone().oneshot(request(Some("a")))
+        );
+        let third = service.clone().oneshot(request(Some("a"))).await;
+        for response in [first, second, third] {
+            let body = response.unwrap().into_body().collect().await.unwrap();
+            assert_eq!(body.to_bytes(), "response 0");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Requests with other keys or without key are sent.
+        service.clone().oneshot(request(Some("b"))).await.unwrap();
+        service.clone().oneshot(request(None)).await.unwrap();
+        service.oneshot(request(None)).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_window_body_too_large() {
+        let service = DedupWindowLayer::new(Duration::from_secs(60))
+            .max_body_size(4)
+            .layer(service_fn(|_request: http::Request<()>| async {
+                Ok::<_, std::convert::Infallible>(http::Response::new(Full::new(
+                    Bytes::from_static(b"Hello"),
+                )))
+            }));
+
+        let error = service.oneshot(request(Some("a"))).await.unwrap_err();
+        let error = error.downcast_ref::<ResponseTooLarge>().unwrap();
+        assert_eq!(error.limit, 4);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_window_shared_error() {
+        let service = DedupWindowLayer::new(Duration::from_secs(60)).layer(service_fn(
+            |_request: http::Request<()>| async {
+                Err::<http::Response<Full<Bytes>>, _>(std::io::Error::from(
+                    std::io::ErrorKind::ConnectionReset,
+                ))
+            },
+        ));
+
+        let error = service.oneshot(request(Some("a"))).await.unwrap_err();
+        let error = error.downcast_ref::<SharedError>().unwrap();
+        let inner = error.inner().downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(inner.kind(), std::io::ErrorKind::ConnectionReset);
+    }
+}
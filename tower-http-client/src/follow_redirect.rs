@@ -0,0 +1,454 @@
+//! Middleware that follows the HTTP redirects.
+//!
+//! Clients like [`reqwest`] follow redirects internally, but the implementation-agnostic
+//! client stacks and the other adapters don't. This middleware reissues the request to the
+//! URI from the `Location` header of the `301`, `302`, `303`, `307` and `308` responses:
+//!
+//! - The `303 See Other` redirect turns the request into the body-less `GET` one, unless
+//!   it is a `HEAD` request. The same is done for the `POST` requests redirected with the
+//!   `301` or `302` status, if [enabled](FollowRedirectLayer::downgrade_post).
+//! - The `307` and `308` redirects preserve the request method and body.
+//! - The `Authorization`, `Proxy-Authorization` and `Cookie` headers are removed when
+//!   the request is redirected to another origin, if [enabled](FollowRedirectLayer::strip_credentials).
+//!
+//! Each hop needs a fresh copy of the request, so the request body must implement the
+//! [`TryCloneBody`] trait. If the body cannot be cloned, the redirect response is returned
+//! as is.
+//!
+//! # Example
+//!
+//! ```
+//! use tower::ServiceBuilder;
+//! use tower_http_client::follow_redirect::FollowRedirectLayer;
+//! use tower_reqwest::HttpClientLayer;
+//!
+//! let client = ServiceBuilder::new()
+//!     .layer(FollowRedirectLayer::new().max_redirects(5))
+//!     .layer(HttpClientLayer)
+//!     .service(reqwest::Client::new());
+//! ```
+//!
+//! [`reqwest`]: https://docs.rs/reqwest
+//! [`TryCloneBody`]: crate::client::TryCloneBody
+
+use std::task::{Context, Poll};
+
+use futures_util::future::BoxFuture;
+use http::{
+    header::{
+        AUTHORIZATION, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, HOST, LOCATION,
+        PROXY_AUTHORIZATION, TRANSFER_ENCODING,
+    },
+    HeaderValue, Method, Request, Response, StatusCode, Uri,
+};
+use thiserror::Error;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::client::{try_clone::try_clone_request, TryCloneBody};
+
+/// Errors returned by the [`FollowRedirect`] middleware.
+#[derive(Debug, Error)]
+pub enum FollowRedirectError<E> {
+    /// An error occurred in the underlying service.
+    #[error(transparent)]
+    Service(E),
+    /// The server redirected the request more times than allowed.
+    #[error("too many redirects, the limit is {max}")]
+    TooManyRedirects {
+        /// The maximum number of redirects.
+        max: u32,
+    },
+    /// The redirect response doesn't contain the `Location` header.
+    #[error("redirect response with the {status} status has no location")]
+    MissingLocation {
+        /// The redirect response status.
+        status: StatusCode,
+    },
+    /// The `Location` header of the redirect response is not a valid `http` or `https` URI.
+    #[error("invalid redirect location {location:?}")]
+    InvalidLocation {
+        /// The `Location` header value.
+        location: HeaderValue,
+    },
+}
+
+/// Layer that applies the [`FollowRedirect`] middleware.
+#[derive(Debug, Clone, Copy)]
+pub struct FollowRedirectLayer {
+    policy: RedirectPolicy,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RedirectPolicy {
+    max_redirects: u32,
+    downgrade_post: bool,
+    strip_credentials: bool,
+}
+
+impl FollowRedirectLayer {
+    /// Creates a new layer with the default settings.
+    ///
+    /// By default, at most 10 redirects are followed, the redirected `POST` requests are
+    /// downgraded to `GET`, and the credentials are not sent to other origins.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            policy: RedirectPolicy::DEFAULT,
+        }
+    }
+
+    /// Sets the maximum number of redirects for a single request.
+    #[must_use]
+    pub const fn max_redirects(mut self, max_redirects: u32) -> Self {
+        self.policy.max_redirects = max_redirects;
+        self
+    }
+
+    /// Sets whether the `POST` requests redirected with the `301` or `302` status should be
+    /// reissued as the body-less `GET` ones, like browsers do.
+    ///
+    /// If disabled, the method and body are preserved as for the `307` and `308` redirects.
+    #[must_use]
+    pub const fn downgrade_post(mut self, downgrade: bool) -> Self {
+        self.policy.downgrade_post = downgrade;
+        self
+    }
+
+    /// Sets whether the `Authorization`, `Proxy-Authorization` and `Cookie` headers should be
+    /// removed when the request is redirected to another origin.
+    #[must_use]
+    pub const fn strip_credentials(mut self, strip: bool) -> Self {
+        self.policy.strip_credentials = strip;
+        self
+    }
+}
+
+impl Default for FollowRedirectLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for FollowRedirectLayer {
+    type Service = FollowRedirect<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FollowRedirect {
+            inner,
+            policy: self.policy,
+        }
+    }
+}
+
+/// Middleware that follows the HTTP redirects.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct FollowRedirect<S> {
+    inner: S,
+    policy: RedirectPolicy,
+}
+
+impl<S> FollowRedirect<S> {
+    /// Creates a new middleware with the default settings.
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            policy: RedirectPolicy::DEFAULT,
+        }
+    }
+}
+
+impl<S, ReqBody, RespBody> Service<Request<ReqBody>> for FollowRedirect<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<RespBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    ReqBody: TryCloneBody + Default + Send + 'static,
+    RespBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = FollowRedirectError<S::Error>;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner
+            .poll_ready(cx)
+            .map_err(FollowRedirectError::Service)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        // Take the service that was ready.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let policy = self.policy;
+
+        Box::pin(async move {
+            let mut redirects = 0;
+            loop {
+                let replay = try_clone_request(&request);
+                let response = inner
+                    .call(request)
+                    .await
+                    .map_err(FollowRedirectError::Service)?;
+
+                let status = response.status();
+                if !is_redirect(status) {
+                    return Ok(response);
+                }
+                let Some(mut next) = replay else {
+                    return Ok(response);
+                };
+                if redirects == policy.max_redirects {
+                    return Err(FollowRedirectError::TooManyRedirects {
+                        max: policy.max_redirects,
+                    });
+                }
+
+                let location = response
+                    .headers()
+                    .get(LOCATION)
+                    .ok_or(FollowRedirectError::MissingLocation { status })?;
+                let target = resolve_location(next.uri(), location).ok_or_else(|| {
+                    FollowRedirectError::InvalidLocation {
+                        location: location.clone(),
+                    }
+                })?;
+                drop(response);
+
+                policy.redirect(&mut next, status, target);
+                futures_util::future::poll_fn(|cx| inner.poll_ready(cx))
+                    .await
+                    .map_err(FollowRedirectError::Service)?;
+                request = next;
+                redirects += 1;
+            }
+        })
+    }
+}
+
+impl RedirectPolicy {
+    const DEFAULT: Self = Self {
+        max_redirects: 10,
+        downgrade_post: true,
+        strip_credentials: true,
+    };
+
+    // Adjusts the request copy to be sent to the redirect target.
+    fn redirect<B: Default>(self, request: &mut Request<B>, status: StatusCode, target: Uri) {
+        let method = request.method();
+        let to_get = (status == StatusCode::SEE_OTHER && method != Method::HEAD)
+            || (self.downgrade_post
+                && method == Method::POST
+                && matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND));
+        if to_get {
+            *request.method_mut() = Method::GET;
+            *request.body_mut() = B::default();
+            for name in [
+                CONTENT_TYPE,
+                CONTENT_LENGTH,
+                CONTENT_ENCODING,
+                TRANSFER_ENCODING,
+            ] {
+                request.headers_mut().remove(name);
+            }
+        }
+
+        if !is_same_origin(request.uri(), &target) {
+            request.headers_mut().remove(HOST);
+            if self.strip_credentials {
+                for name in [AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE] {
+                    request.headers_mut().remove(name);
+                }
+            }
+        }
+        *request.uri_mut() = target;
+    }
+}
+
+fn is_redirect(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::SEE_OTHER
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT
+    )
+}
+
+// Resolves the possibly relative location against the request URI.
+fn resolve_location(base: &Uri, location: &HeaderValue) -> Option<Uri> {
+    let location = location.to_str().ok()?;
+    let mut target = match url::Url::parse(&base.to_string()) {
+        Ok(base) => base.join(location),
+        Err(_) => url::Url::parse(location),
+    }
+    .ok()?;
+    if !matches!(target.scheme(), "http" | "https") {
+        return None;
+    }
+    target.set_fragment(None);
+    Uri::try_from(target.as_str()).ok()
+}
+
+fn is_same_origin(a: &Uri, b: &Uri) -> bool {
+    let port = |uri: &Uri| {
+        uri.port_u16().or(match uri.scheme_str() {
+            Some("http") => Some(80),
+            Some("https") => Some(443),
+            _ => None,
+        })
+    };
+    a.scheme() == b.scheme() && a.host() == b.host() && port(a) == port(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::{Arc, Mutex},
+    };
+
+    use bytes::Bytes;
+    use http::{header::LOCATION, Method, Request, Response, StatusCode};
+    use tower::{service_fn, ServiceExt as _};
+    use tower_layer::Layer as _;
+
+    use super::{FollowRedirectError, FollowRedirectLayer};
+
+    type Requests = Arc<Mutex<Vec<Request<Bytes>>>>;
+
+    // Returns a service which responds with the given redirects in order and then with `200`.
+    fn redirects(
+        redirects: &'static [(StatusCode, Option<&'static str>)],
+        requests: Requests,
+    ) -> impl tower_service::Service<
+        Request<Bytes>,
+        Response = Response<()>,
+        Error = Infallible,
+        Future = impl Send,
+    > + Clone {
+        service_fn(move |request: Request<Bytes>| {
+            let mut requests = requests.lock().unwrap();
+            let redirect = redirects.get(requests.len()).copied();
+            requests.push(request);
+            async move {
+                let mut response = Response::builder();
+                if let Some((status, location)) = redirect {
+                    response = response.status(status);
+                    if let Some(location) = location {
+                        response = response.header(LOCATION, location);
+                    }
+                }
+                Ok(response.body(()).unwrap())
+            }
+        })
+    }
+
+    fn post(uri: &str) -> Request<Bytes> {
+        Request::post(uri)
+            .header("authorization", "Bearer token")
+            .header("content-type", "text/plain")
+            .body(Bytes::from_static(b"payload"))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_follow_redirect_downgrade_to_get() {
+        let requests = Requests::default();
+        let service = FollowRedirectLayer::new().layer(redirects(
+            &[(StatusCode::FOUND, Some("/next?page=2#top"))],
+            requests.clone(),
+        ));
+
+        let response = service.oneshot(post("http://example.com/start")).await;
+        assert_eq!(response.unwrap().status(), StatusCode::OK);
+
+        let requests = requests.lock().unwrap();
+        let redirected = &requests[1];
+        assert_eq!(redirected.method(), Method::GET);
+        assert_eq!(redirected.uri(), "http://example.com/next?page=2");
+        assert_eq!(redirected.body(), "");
+        assert!(!redirected.headers().contains_key("content-type"));
+        // The same origin keeps the credentials.
+        assert_eq!(redirected.headers()["authorization"], "Bearer token");
+    }
+
+    #[tokio::test]
+    async fn test_follow_redirect_preserve_method() {
+        let requests = Requests::default();
+        let service = FollowRedirectLayer::new().layer(redirects(
+            &[
+                (StatusCode::TEMPORARY_REDIRECT, Some("https://example.com/")),
+                (StatusCode::PERMANENT_REDIRECT, Some("https://other.com/")),
+            ],
+            requests.clone(),
+        ));
+
+        let response = service.oneshot(post("http://example.com/start")).await;
+        assert_eq!(response.unwrap().status(), StatusCode::OK);
+
+        let requests = requests.lock().unwrap();
+        for request in &requests[1..] {
+            assert_eq!(request.method(), Method::POST);
+            assert_eq!(request.body(), "payload");
+        }
+        // Another scheme is another origin.
+        assert!(!requests[1].headers().contains_key("authorization"));
+        assert_eq!(requests[2].uri(), "https://other.com/");
+    }
+
+    #[tokio::test]
+    async fn test_follow_redirect_errors() {
+        const LOOP: (StatusCode, Option<&str>) = (StatusCode::MOVED_PERMANENTLY, Some("/loop"));
+        let service = FollowRedirectLayer::new()
+            .max_redirects(2)
+            .layer(redirects(&[LOOP; 3], Requests::default()));
+        let error = service
+            .oneshot(
+                Request::get("http://example.com/")
+                    .body(Bytes::new())
+                    .unwrap(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            FollowRedirectError::TooManyRedirects { max: 2 }
+        ));
+
+        let service = FollowRedirectLayer::new().layer(redirects(
+            &[(StatusCode::SEE_OTHER, None)],
+            Requests::default(),
+        ));
+        let error = service
+            .oneshot(Request::new(Bytes::new()))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            FollowRedirectError::MissingLocation {
+                status: StatusCode::SEE_OTHER
+            }
+        ));
+
+        let service = FollowRedirectLayer::new().layer(redirects(
+            &[(StatusCode::FOUND, Some("ftp://example.com/file"))],
+            Requests::default(),
+        ));
+        let error = service
+            .oneshot(
+                Request::get("http://example.com/")
+                    .body(Bytes::new())
+                    .unwrap(),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid redirect location \"ftp://example.com/file\""
+        );
+    }
+}
@@ -13,8 +13,46 @@
 
 pub use client::{ResponseExt, ServiceExt};
 
-#[cfg(feature = "reqwest")]
+#[cfg(any(feature = "hyper", feature = "reqwest"))]
 pub mod adapters;
+#[cfg(feature = "digest")]
+pub mod auth;
+pub mod base_uri;
+pub mod browser_preset;
+#[cfg(feature = "tracing")]
+pub mod check_content_type;
 pub mod client;
+#[cfg(any(feature = "limit", feature = "retry"))]
+pub mod clock;
+#[cfg(feature = "cookies")]
+pub mod cookie_jar;
+pub mod dedup_window;
+pub mod follow_redirect;
+#[cfg(feature = "limit")]
+pub mod limit;
+pub mod load_shed;
+pub mod metrics;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod request_body_limit;
+#[cfg(feature = "retry")]
+pub mod retry;
+pub mod set_header;
+pub mod strip_hop_by_hop;
+#[cfg(feature = "tracing")]
+pub mod trace;
 #[cfg(feature = "util")]
 pub mod util;
+
+#[cfg(any(
+    feature = "charset",
+    feature = "json",
+    feature = "multipart",
+    feature = "tracing"
+))]
+mod mime;
+#[cfg(any(feature = "digest", feature = "multipart", feature = "retry"))]
+mod random;
+
+/// Alias for a type-erased error type.
+pub(crate) type BoxError = Box<dyn std::error::Error + Send + Sync>;
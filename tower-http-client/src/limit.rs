@@ -0,0 +1,413 @@
+//! Middleware that limits both the number of in-flight requests and the request rate.
+//!
+//! The [`tower`] `ConcurrencyLimit` and `RateLimit` middlewares keep their state per
+//! service instance, so the rate limit is not shared between clones and the combined stack
+//! has to be wrapped into a `Buffer` to be used from several tasks. The
+//! [`ConcurrencyAndRate`] middleware keeps the state behind the shared reference instead:
+//! all services created by the same [`ConcurrencyAndRateLayer`], and all their clones,
+//! share the same limits.
+//!
+//! The service is always ready, the limits are applied by the response future:
+//!
+//! - If both an in-flight slot and a rate permit are available, the request is sent
+//!   immediately.
+//! - Otherwise the request waits in the queue, if there is a free place in it. By default
+//!   the queue is disabled.
+//! - Otherwise the request fails with the [`RateLimited`] error without reaching the inner
+//!   service.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use tower::ServiceBuilder;
+//! use tower_http_client::limit::ConcurrencyAndRateLayer;
+//! use tower_reqwest::HttpClientLayer;
+//!
+//! let limit = ConcurrencyAndRateLayer::new(5, 2, Duration::from_secs(1)).queue(64);
+//! let client = ServiceBuilder::new()
+//!     .layer(limit.clone())
+//!     .layer(HttpClientLayer)
+//!     .service(reqwest::Client::new());
+//!
+//! // The layer reports the state of the limits shared by its services.
+//! println!("{} in-flight slots available", limit.available_permits());
+//! ```
+//!
+//! [`tower`]: https://docs.rs/tower/latest/tower/limit/index.html
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures_util::future::BoxFuture;
+use thiserror::Error;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{
+    clock::{Clock, SharedClock},
+    BoxError,
+};
+
+/// The request was rejected because the limits were reached and the queue was full.
+#[derive(Debug, Default, Error)]
+#[error("rate limited")]
+#[non_exhaustive]
+pub struct RateLimited;
+
+impl RateLimited {
+    /// Creates a new error.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+/// Layer that applies the [`ConcurrencyAndRate`] middleware.
+///
+/// All services created by this layer share the same limits.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyAndRateLayer {
+    limits: Arc<Limits>,
+    max_queued: usize,
+    clock: SharedClock,
+}
+
+impl ConcurrencyAndRateLayer {
+    /// Creates a new layer, which allows at most `max_in_flight` requests at once and
+    /// at most `num` requests per the `per` duration.
+    #[must_use]
+    pub fn new(max_in_flight: usize, num: u64, per: Duration) -> Self {
+        Self {
+            limits: Arc::new(Limits::new(max_in_flight, num, per)),
+            max_queued: 0,
+            clock: SharedClock::default(),
+        }
+    }
+
+    /// Sets the maximum number of requests waiting for the limits.
+    ///
+    /// By default the queue is disabled, so the requests exceeding the limits fail
+    /// immediately.
+    #[must_use]
+    pub const fn queue(mut self, max_depth: usize) -> Self {
+        self.max_queued = max_depth;
+        self
+    }
+
+    /// Sets the clock which is used to measure the rate intervals and to wait for them.
+    ///
+    /// By default, the [`TokioClock`](crate::clock::TokioClock) is used.
+    #[must_use]
+    pub fn clock(mut self, clock: impl Clock) -> Self {
+        self.clock = SharedClock::new(clock);
+        self
+    }
+
+    /// Returns the number of the available in-flight slots.
+    #[must_use]
+    pub fn available_permits(&self) -> usize {
+        self.limits.semaphore.available_permits()
+    }
+
+    /// Returns the number of requests, which can be sent in the current rate interval.
+    #[must_use]
+    pub fn remaining_rate(&self) -> u64 {
+        self.limits.remaining_rate(self.clock.0.instant())
+    }
+
+    /// Returns the number of requests waiting for the limits.
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.limits.queued.load(Ordering::Relaxed)
+    }
+}
+
+impl<S> Layer<S> for ConcurrencyAndRateLayer {
+    type Service = ConcurrencyAndRate<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConcurrencyAndRate {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Middleware that limits both the number of in-flight requests and the request rate.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyAndRate<S> {
+    inner: S,
+    layer: ConcurrencyAndRateLayer,
+}
+
+impl<S> ConcurrencyAndRate<S> {
+    /// Creates a new middleware, which allows at most `max_in_flight` requests at once and
+    /// at most `num` requests per the `per` duration.
+    pub fn new(inner: S, max_in_flight: usize, num: u64, per: Duration) -> Self {
+        ConcurrencyAndRateLayer::new(max_in_flight, num, per).layer(inner)
+    }
+
+    /// Returns the layer, which shares the limits with this middleware.
+    ///
+    /// It can be used to inspect the limits state.
+    pub const fn layer(&self) -> &ConcurrencyAndRateLayer {
+        &self.layer
+    }
+}
+
+impl<S, Req> Service<Req> for ConcurrencyAndRate<S>
+where
+    S: Service<Req> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+    Req: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        // Take the service that was ready.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let limits = self.layer.limits.clone();
+        let max_queued = self.layer.max_queued;
+        let clock = self.layer.clock.clone();
+
+        Box::pin(async move {
+            let _permit = limits.acquire(max_queued, &*clock.0).await?;
+            inner.call(req).await.map_err(Into::into)
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Limits {
+    semaphore: Arc<Semaphore>,
+    rate: Mutex<RateWindow>,
+    queued: AtomicUsize,
+}
+
+// Fixed rate window, the same as the `tower` one uses.
+#[derive(Debug)]
+struct RateWindow {
+    num: u64,
+    per: Duration,
+    // The end of the current window, `None` until the first request.
+    until: Option<Instant>,
+    remaining: u64,
+}
+
+impl Limits {
+    fn new(max_in_flight: usize, num: u64, per: Duration) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_in_flight)),
+            rate: Mutex::new(RateWindow {
+                num,
+                per,
+                until: None,
+                remaining: num,
+            }),
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    async fn acquire(
+        &self,
+        max_queued: usize,
+        clock: &dyn Clock,
+    ) -> Result<OwnedSemaphorePermit, RateLimited> {
+        let permit = self.semaphore.clone().try_acquire_owned().ok();
+        if permit.is_some() && self.try_take_rate(clock.instant()).is_ok() {
+            return permit.ok_or(RateLimited);
+        }
+
+        // The permit taken is kept while waiting for the rate.
+        let _queued = self.enqueue(max_queued).ok_or(RateLimited)?;
+        let permit = match permit {
+            Some(permit) => permit,
+            None => self
+                .semaphore
+                .clone()
+                .acquire_owned()
+                .await
+                .map_err(|_| RateLimited)?,
+        };
+        while let Err(until) = self.try_take_rate(clock.instant()) {
+            clock
+                .sleep(until.saturating_duration_since(clock.instant()))
+                .await;
+        }
+        Ok(permit)
+    }
+
+    // Takes a rate permit or returns the instant when the next window starts.
+    fn try_take_rate(&self, now: Instant) -> Result<(), Instant> {
+        let mut rate = self.rate.lock().unwrap_or_else(PoisonError::into_inner);
+        let until = match rate.until {
+            Some(until) if now < until => until,
+            _ => {
+                let until = now + rate.per;
+                rate.until = Some(until);
+                rate.remaining = rate.num;
+                until
+            }
+        };
+        if rate.remaining == 0 {
+            return Err(until);
+        }
+        rate.remaining -= 1;
+        Ok(())
+    }
+
+    fn remaining_rate(&self, now: Instant) -> u64 {
+        let rate = self.rate.lock().unwrap_or_else(PoisonError::into_inner);
+        match rate.until {
+            Some(until) if now < until => rate.remaining,
+            _ => rate.num,
+        }
+    }
+
+    fn enqueue(&self, max_queued: usize) -> Option<QueueGuard<'_>> {
+        self.queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                (queued < max_queued).then_some(queued + 1)
+            })
+            .ok()
+            .map(|_| QueueGuard(&self.queued))
+    }
+}
+
+struct QueueGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueueGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant, SystemTime},
+    };
+
+    use futures_util::future::BoxFuture;
+    use tokio::sync::oneshot;
+    use tower::{service_fn, ServiceExt as _};
+    use tower_layer::Layer as _;
+    use tower_service::Service as _;
+
+    use super::{ConcurrencyAndRate, ConcurrencyAndRateLayer, RateLimited};
+    use crate::clock::Clock;
+
+    // Clock which advances the time on sleep instead of waiting.
+    #[derive(Clone)]
+    struct ManualClock {
+        start: Instant,
+        elapsed: Arc<Mutex<Duration>>,
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> SystemTime {
+            SystemTime::UNIX_EPOCH + *self.elapsed.lock().unwrap()
+        }
+
+        fn instant(&self) -> Instant {
+            self.start + *self.elapsed.lock().unwrap()
+        }
+
+        fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+            *self.elapsed.lock().unwrap() += duration;
+            Box::pin(std::future::ready(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_and_rate_queue() {
+        let (release, released) = oneshot::channel::<()>();
+        let released = Arc::new(Mutex::new(Some(released)));
+        let inner = service_fn(move |request: u32| {
+            let released = released.lock().unwrap().take();
+            async move {
+                if let Some(released) = released {
+                    released.await.unwrap();
+                }
+                Ok::<_, Infallible>(request)
+            }
+        });
+        let limits = ConcurrencyAndRateLayer::new(1, 100, Duration::from_secs(1)).queue(1);
+        let mut service = limits.layer(inner);
+
+        // The first request holds the only slot, the second one waits in the queue.
+        let first = tokio::spawn(service.ready().await.unwrap().call(1));
+        tokio::task::yield_now().await;
+        let second = tokio::spawn(service.clone().oneshot(2));
+        tokio::task::yield_now().await;
+        assert_eq!(limits.available_permits(), 0);
+        assert_eq!(limits.queued(), 1);
+
+        let error = service.clone().oneshot(3).await.unwrap_err();
+        assert!(error.is::<RateLimited>());
+
+        release.send(()).unwrap();
+        assert_eq!(first.await.unwrap().unwrap(), 1);
+        assert_eq!(second.await.unwrap().unwrap(), 2);
+        assert_eq!(limits.available_permits(), 1);
+        assert_eq!(limits.queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_and_rate_interval() {
+        let inner = service_fn(|request: u32| async move { Ok::<_, Infallible>(request) });
+        let service = ConcurrencyAndRate::new(inner, 10, 2, Duration::from_millis(50));
+
+        service.clone().oneshot(1).await.unwrap();
+        service.clone().oneshot(2).await.unwrap();
+        assert_eq!(service.layer().remaining_rate(), 0);
+        // No queue, so the exceeding request fails.
+        let error = service.clone().oneshot(3).await.unwrap_err();
+        assert!(error.is::<RateLimited>());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(service.layer().remaining_rate(), 2);
+        service.oneshot(4).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_and_rate_clock() {
+        let clock = ManualClock {
+            start: Instant::now(),
+            elapsed: Arc::default(),
+        };
+        let inner = service_fn(|request: u32| async move { Ok::<_, Infallible>(request) });
+        let limits = ConcurrencyAndRateLayer::new(10, 2, Duration::from_secs(60))
+            .queue(1)
+            .clock(clock.clone());
+        let service = limits.layer(inner);
+
+        for request in 0..5 {
+            assert_eq!(service.clone().oneshot(request).await.unwrap(), request);
+        }
+        // The exceeding requests waited for two intervals without real sleeping.
+        assert_eq!(*clock.elapsed.lock().unwrap(), Duration::from_secs(120));
+        assert_eq!(limits.remaining_rate(), 1);
+    }
+}
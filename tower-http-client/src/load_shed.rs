@@ -0,0 +1,81 @@
+//! Middleware that rejects requests immediately when the inner service is not ready.
+//!
+//! By default, a caller waits for the client readiness before sending a request, so under
+//! the overload the requests are queued without a bound. This middleware reports readiness
+//! all the time instead. If the inner service is not ready at the moment, the request
+//! fails fast with the [`Overloaded`] error without reaching it.
+//!
+//! The middleware is useful in pair with the [`ConcurrencyLimit`] one, so the requests
+//! exceeding the limit are shed instead of waiting for a free slot. Note that the load
+//! shedding layer must be placed above the limiting one.
+//!
+//! # Example
+//!
+//! ```
+//! use tower::ServiceBuilder;
+//! use tower_http_client::load_shed::LoadShedLayer;
+//! use tower_reqwest::HttpClientLayer;
+//!
+//! let client = ServiceBuilder::new()
+//!     .layer(LoadShedLayer::new())
+//!     .concurrency_limit(16)
+//!     .layer(HttpClientLayer)
+//!     .service(reqwest::Client::new());
+//! ```
+//!
+//! The shed requests may be told apart from the other failures by downcasting the error:
+//!
+//! ```
+//! use tower_http_client::load_shed::Overloaded;
+//!
+//! fn is_overloaded(error: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+//!     error.is::<Overloaded>()
+//! }
+//! ```
+//!
+//! This module re-exports the [`tower::load_shed`] middleware, so it is available without
+//! depending on `tower` directly.
+//!
+//! [`ConcurrencyLimit`]: https://docs.rs/tower/latest/tower/limit/struct.ConcurrencyLimit.html
+//! [`tower::load_shed`]: https://docs.rs/tower/latest/tower/load_shed/index.html
+
+pub use tower::load_shed::{error::Overloaded, future::ResponseFuture, LoadShed, LoadShedLayer};
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use tokio::sync::oneshot;
+    use tower::{service_fn, ServiceBuilder, ServiceExt as _};
+    use tower_service::Service as _;
+
+    use super::{LoadShedLayer, Overloaded};
+
+    #[tokio::test]
+    async fn test_load_shed_overloaded() {
+        let (release, released) = oneshot::channel::<()>();
+        let mut released = Some(released);
+        let mut service = ServiceBuilder::new()
+            .layer(LoadShedLayer::new())
+            .concurrency_limit(1)
+            .service(service_fn(move |request: u32| {
+                let released = released.take();
+                async move {
+                    if let Some(released) = released {
+                        released.await.unwrap();
+                    }
+                    Ok::<_, Infallible>(request)
+                }
+            }));
+
+        // The first request holds the only slot.
+        let first = service.ready().await.unwrap().call(1);
+        let error = service.ready().await.unwrap().call(2).await.unwrap_err();
+        assert!(error.is::<Overloaded>());
+
+        release.send(()).unwrap();
+        assert_eq!(first.await.unwrap(), 1);
+        let response = service.ready().await.unwrap().call(3).await.unwrap();
+        assert_eq!(response, 3);
+    }
+}
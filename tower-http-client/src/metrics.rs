@@ -0,0 +1,423 @@
+//! Middleware that collects the per-request metrics.
+//!
+//! For each request the middleware measures the number of the sent and received body bytes,
+//! the response status and the total latency, and passes them to the user-provided hook as
+//! the [`RequestMetrics`]. It may be used to feed the service dashboards, for example, via
+//! the [`metrics`] crate.
+//!
+//! The measurement is completed lazily: both bodies are wrapped to count the data frames as
+//! they flow, and the hook is invoked once the response body is read to the end, not when
+//! the response headers arrive. So the latency covers the whole exchange from the service
+//! call to the body completion.
+//!
+//! The hook is invoked exactly once per request, even if the request fails, or the response
+//! future or body is dropped before the completion. Such cases are reported with the
+//! [`completed`](RequestMetrics::completed) flag unset.
+//!
+//! Note that the counted bytes are the ones yielded by the inner bodies, so the middleware
+//! placed above a decompressing layer reports the decoded response size.
+//!
+//! The inner service receives the requests with the [`RequestBody`] wrapper, so the clients
+//! expecting the concrete body type, like the `reqwest` one, need the body to be converted
+//! back, for example, by the [`tower-http`] `MapRequestBody` middleware.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use tower::ServiceBuilder;
+//! use tower_http::ServiceBuilderExt as _;
+//! use tower_http_client::{
+//!     metrics::{MetricsLayer, RequestBody},
+//!     ResponseExt as _, ServiceExt as _,
+//! };
+//! use tower_reqwest::{into_reqwest_body_sized, HttpClientLayer};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let mut client = ServiceBuilder::new()
+//!         .layer(MetricsLayer::new(|metrics| {
+//!             println!(
+//!                 "{} {:?}: sent {} bytes, received {} bytes in {:?}",
+//!                 metrics.method,
+//!                 metrics.status,
+//!                 metrics.bytes_sent,
+//!                 metrics.bytes_received,
+//!                 metrics.latency,
+//!             );
+//!         }))
+//!         .map_request_body(into_reqwest_body_sized::<RequestBody<reqwest::Body>>)
+//!         .layer(HttpClientLayer)
+//!         .service(reqwest::Client::new());
+//!
+//!     // The metrics are reported once the response body is read.
+//!     let response = client.get("http://example.com").send()?.await?;
+//!     response.drain().await?;
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`metrics`]: https://docs.rs/metrics
+//! [`tower-http`]: https://docs.rs/tower-http/latest/tower_http/map_request_body/index.html
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use bytes::Buf;
+use http::{Method, Request, Response, StatusCode};
+use http_body::{Body, Frame, SizeHint};
+use pin_project::pin_project;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// The metrics of a single request passed to the [`Metrics`] hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RequestMetrics {
+    /// The request method.
+    pub method: Method,
+    /// The number of the request body bytes read by the inner service.
+    pub bytes_sent: u64,
+    /// The number of the response body bytes read by the caller.
+    pub bytes_received: u64,
+    /// The response status, or `None` if the request failed.
+    pub status: Option<StatusCode>,
+    /// The time from the service call to the response body completion.
+    pub latency: Duration,
+    /// Whether the response body has been read to the end.
+    ///
+    /// It is unset if the request or the response body failed, or the response was dropped
+    /// before the body completion.
+    pub completed: bool,
+}
+
+// The hook invoked when the request is completed.
+#[derive(Clone)]
+struct OnComplete(Arc<dyn Fn(&RequestMetrics) + Send + Sync>);
+
+impl fmt::Debug for OnComplete {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnComplete").finish_non_exhaustive()
+    }
+}
+
+/// Layer that applies the [`Metrics`] middleware.
+#[derive(Debug, Clone)]
+pub struct MetricsLayer {
+    on_complete: OnComplete,
+}
+
+impl MetricsLayer {
+    /// Creates a new layer with the hook invoked when the request is completed.
+    #[must_use]
+    pub fn new<F>(on_complete: F) -> Self
+    where
+        F: Fn(&RequestMetrics) + Send + Sync + 'static,
+    {
+        Self {
+            on_complete: OnComplete(Arc::new(on_complete)),
+        }
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = Metrics<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Metrics {
+            inner,
+            on_complete: self.on_complete.clone(),
+        }
+    }
+}
+
+/// Middleware that collects the per-request metrics.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct Metrics<S> {
+    inner: S,
+    on_complete: OnComplete,
+}
+
+impl<S> Metrics<S> {
+    /// Creates a new middleware with the hook invoked when the request is completed.
+    pub fn new<F>(inner: S, on_complete: F) -> Self
+    where
+        F: Fn(&RequestMetrics) + Send + Sync + 'static,
+    {
+        MetricsLayer::new(on_complete).layer(inner)
+    }
+}
+
+impl<S, ReqBody, RespBody> Service<Request<ReqBody>> for Metrics<S>
+where
+    S: Service<Request<RequestBody<ReqBody>>, Response = Response<RespBody>>,
+{
+    type Response = Response<ResponseBody<RespBody>>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let bytes_sent = Arc::new(AtomicU64::new(0));
+        let recorder = Recorder {
+            on_complete: self.on_complete.clone(),
+            method: request.method().clone(),
+            start: Instant::now(),
+            bytes_sent: bytes_sent.clone(),
+            bytes_received: 0,
+            status: None,
+            done: false,
+        };
+        let request = request.map(|inner| RequestBody { inner, bytes_sent });
+
+        ResponseFuture {
+            fut: self.inner.call(request),
+            recorder: Some(recorder),
+        }
+    }
+}
+
+/// Response future for the [`Metrics`] middleware.
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    fut: F,
+    recorder: Option<Recorder>,
+}
+
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+{
+    type Output = Result<Response<ResponseBody<B>>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = std::task::ready!(this.fut.poll(cx));
+        // The failed request is reported once the recorder is dropped.
+        let mut recorder = this
+            .recorder
+            .take()
+            .expect("`ResponseFuture` polled after completion");
+
+        Poll::Ready(result.map(|response| {
+            recorder.status = Some(response.status());
+            response.map(|inner| ResponseBody { inner, recorder })
+        }))
+    }
+}
+
+/// A request body wrapper that counts the bytes read by the inner service.
+#[pin_project]
+#[derive(Debug)]
+pub struct RequestBody<B> {
+    #[pin]
+    inner: B,
+    bytes_sent: Arc<AtomicU64>,
+}
+
+impl<B: Body> Body for RequestBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = std::task::ready!(this.inner.poll_frame(cx));
+        if let Some(data) = frame
+            .as_ref()
+            .and_then(|frame| frame.as_ref().ok()?.data_ref())
+        {
+            this.bytes_sent
+                .fetch_add(data.remaining() as u64, Ordering::Relaxed);
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// A response body wrapper that counts the bytes read by the caller and reports the
+/// metrics once the body is completed or dropped.
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseBody<B> {
+    #[pin]
+    inner: B,
+    recorder: Recorder,
+}
+
+impl<B: Body> Body for ResponseBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        let frame = std::task::ready!(this.inner.as_mut().poll_frame(cx));
+        match &frame {
+            Some(Ok(frame)) => {
+                if let Some(data) = frame.data_ref() {
+                    this.recorder.bytes_received += data.remaining() as u64;
+                }
+                // The caller may stop polling the body which reports its end in advance.
+                if this.inner.is_end_stream() {
+                    this.recorder.report(true);
+                }
+            }
+            Some(Err(_)) => this.recorder.report(false),
+            None => this.recorder.report(true),
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// Collects the metrics of a single request and reports them exactly once.
+#[derive(Debug)]
+struct Recorder {
+    on_complete: OnComplete,
+    method: Method,
+    start: Instant,
+    bytes_sent: Arc<AtomicU64>,
+    bytes_received: u64,
+    status: Option<StatusCode>,
+    done: bool,
+}
+
+impl Recorder {
+    fn report(&mut self, completed: bool) {
+        if std::mem::replace(&mut self.done, true) {
+            return;
+        }
+
+        let metrics = RequestMetrics {
+            method: self.method.clone(),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received,
+            status: self.status,
+            latency: self.start.elapsed(),
+            completed,
+        };
+        (self.on_complete.0)(&metrics);
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.report(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use bytes::Bytes;
+    use http::{Request, Response, StatusCode};
+    use http_body_util::{BodyExt as _, Full};
+    use tower::{service_fn, ServiceExt as _};
+
+    use super::{Metrics, RequestBody, RequestMetrics};
+
+    type Reports = Arc<Mutex<Vec<RequestMetrics>>>;
+
+    async fn echo_twice(
+        request: Request<RequestBody<Full<Bytes>>>,
+    ) -> Result<Response<Full<Bytes>>, &'static str> {
+        if request.uri().path() == "/fail" {
+            return Err("request failed");
+        }
+        let body = request.into_body().collect().await.unwrap().to_bytes();
+        Ok(Response::new(Full::new(body.repeat(2).into())))
+    }
+
+    fn record(reports: &Reports) -> impl Fn(&RequestMetrics) + Send + Sync + 'static {
+        let reports = reports.clone();
+        move |metrics| reports.lock().unwrap().push(metrics.clone())
+    }
+
+    #[tokio::test]
+    async fn test_metrics_completed() {
+        let reports = Reports::default();
+        let request = Request::new(Full::new(Bytes::from_static(b"hello")));
+        let response = Metrics::new(service_fn(echo_twice), record(&reports))
+            .oneshot(request)
+            .await
+            .unwrap();
+        // The metrics are reported only after the body is read.
+        assert!(reports.lock().unwrap().is_empty());
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "hellohello");
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].bytes_sent, 5);
+        assert_eq!(reports[0].bytes_received, 10);
+        assert_eq!(reports[0].status, Some(StatusCode::OK));
+        assert!(reports[0].completed);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_incomplete() {
+        let reports = Reports::default();
+
+        // The response body is dropped before it is read.
+        let request = Request::new(Full::new(Bytes::from_static(b"hello")));
+        let response = Metrics::new(service_fn(echo_twice), record(&reports))
+            .oneshot(request)
+            .await
+            .unwrap();
+        drop(response);
+        // The request fails.
+        let request = Request::builder()
+            .uri("/fail")
+            .body(Full::new(Bytes::new()))
+            .unwrap();
+        Metrics::new(service_fn(echo_twice), record(&reports))
+            .oneshot(request)
+            .await
+            .unwrap_err();
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].bytes_sent, 5);
+        assert_eq!(reports[0].bytes_received, 0);
+        assert_eq!(reports[0].status, Some(StatusCode::OK));
+        assert!(!reports[0].completed);
+        assert_eq!(reports[1].status, None);
+        assert!(!reports[1].completed);
+    }
+}
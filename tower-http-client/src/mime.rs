@@ -0,0 +1,67 @@
+//! Helpers for inspecting media types in the header values.
+
+use http::HeaderValue;
+
+/// Returns the media type without parameters.
+#[cfg_attr(not(any(feature = "json", feature = "tracing")), allow(dead_code))]
+pub(crate) fn media_type(value: &str) -> &str {
+    value.split(';').next().unwrap_or_default().trim()
+}
+
+/// Returns the parameter with the given name of the `Content-Type` header value.
+#[cfg_attr(not(any(feature = "charset", feature = "multipart")), allow(dead_code))]
+pub(crate) fn parameter<'a>(value: &'a str, name: &str) -> Option<&'a str> {
+    value.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Checks that the given media type describes JSON.
+#[cfg_attr(not(any(feature = "json", feature = "tracing")), allow(dead_code))]
+pub(crate) fn is_json_media_type(media_type: &str) -> bool {
+    media_type.eq_ignore_ascii_case("application/json")
+        || media_type
+            .get(media_type.len().saturating_sub(5)..)
+            .is_some_and(|suffix| suffix.eq_ignore_ascii_case("+json"))
+}
+
+/// Checks that the given media type describes XML.
+#[cfg_attr(not(all(feature = "json", feature = "xml")), allow(dead_code))]
+pub(crate) fn is_xml_media_type(media_type: &str) -> bool {
+    media_type.eq_ignore_ascii_case("application/xml")
+        || media_type.eq_ignore_ascii_case("text/xml")
+        || media_type
+            .get(media_type.len().saturating_sub(4)..)
+            .is_some_and(|suffix| suffix.eq_ignore_ascii_case("+xml"))
+}
+
+/// Checks that the `Content-Type` header value describes JSON.
+#[cfg_attr(not(any(feature = "json", feature = "tracing")), allow(dead_code))]
+pub(crate) fn is_json(value: &HeaderValue) -> bool {
+    value
+        .to_str()
+        .is_ok_and(|value| is_json_media_type(media_type(value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parameter;
+
+    #[test]
+    fn test_mime_parameter() {
+        let charset = |value| parameter(value, "charset");
+        assert_eq!(
+            charset("text/html; charset=windows-1252"),
+            Some("windows-1252")
+        );
+        assert_eq!(
+            charset("text/plain;CHARSET=\"iso-8859-1\""),
+            Some("iso-8859-1")
+        );
+        assert_eq!(charset("text/plain; format=flowed"), None);
+        assert_eq!(charset("text/plain"), None);
+    }
+}
@@ -0,0 +1,507 @@
+//! In-memory HTTP client for testing without network.
+//!
+//! The [`MockClientService`] works on the [`http::Request`] and [`http::Response`] level,
+//! so it can replace the real client adapter in a [`ServiceBuilder`] stack to test the
+//! middlewares and request building code. It matches each received request against the
+//! registered [`Mock`]s in the registration order and returns the response of the first
+//! matching one. Requests not matching any mock fail with the [`MockError::Unmatched`]
+//! error.
+//!
+//! All received requests are recorded with their bodies, and the expected number of calls
+//! can be checked via the [`MockClientService::verify`] method.
+//!
+//! # Example
+//!
+//! ```
+//! use http::{Method, StatusCode};
+//! use tower_http_client::{
+//!     mock::{Mock, MockClientService},
+//!     ResponseExt as _, ServiceExt as _,
+//! };
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let mut client = MockClientService::new();
+//!     client.register(
+//!         Mock::new()
+//!             .method(Method::GET)
+//!             .path("/hello")
+//!             .respond_with(http::Response::new("Hello world".into()))
+//!             .expect(1),
+//!     );
+//!
+//!     let response = client.get("http://localhost/hello").send()?.await?;
+//!     assert_eq!(response.status(), StatusCode::OK);
+//!     assert_eq!(response.utf8().await?, "Hello world");
+//!
+//!     client.verify();
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`ServiceBuilder`]: tower::ServiceBuilder
+
+use std::{
+    fmt,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use http::{
+    HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode, Uri, Version,
+};
+use http_body::Body;
+use http_body_util::{BodyExt as _, Full};
+use thiserror::Error;
+use tower_service::Service;
+
+use crate::BoxError;
+
+/// Errors returned by the [`MockClientService`].
+#[derive(Debug, Error)]
+pub enum MockError {
+    /// No registered mock matches the request.
+    #[error("no mock matches the `{method} {uri}` request")]
+    Unmatched {
+        /// The request method.
+        method: Method,
+        /// The request URI.
+        uri: Uri,
+    },
+    /// The matched mock is configured to fail via the [`Mock::fail_with`] method.
+    #[error("{0}")]
+    Failure(String),
+    /// An error occurred while reading the request body.
+    #[error("failed to read the request body")]
+    Body(#[source] BoxError),
+}
+
+// Predicate over the received request.
+type Matcher = Arc<dyn Fn(&Request<Bytes>) -> bool + Send + Sync>;
+
+/// A request matcher with the canned response for the [`MockClientService`].
+///
+/// By default the mock matches all requests and responds with the empty `200 OK` response.
+#[derive(Clone)]
+pub struct Mock {
+    method: Option<Method>,
+    path: Option<String>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    body: Option<Bytes>,
+    matchers: Vec<Matcher>,
+    response: Result<CannedResponse, String>,
+    expected: Option<usize>,
+}
+
+impl fmt::Debug for Mock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mock")
+            .field("method", &self.method)
+            .field("path", &self.path)
+            .field("headers", &self.headers)
+            .field("body", &self.body)
+            .field("matchers", &self.matchers.len())
+            .field("response", &self.response)
+            .field("expected", &self.expected)
+            .finish()
+    }
+}
+
+impl Default for Mock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mock {
+    /// Creates a new mock, which matches all requests.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            method: None,
+            path: None,
+            headers: Vec::new(),
+            body: None,
+            matchers: Vec::new(),
+            response: Ok(CannedResponse {
+                status: StatusCode::OK,
+                version: Version::HTTP_11,
+                headers: HeaderMap::new(),
+                body: Bytes::new(),
+            }),
+            expected: None,
+        }
+    }
+
+    /// Matches requests with the given method.
+    #[must_use]
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    /// Matches requests with the given URI path.
+    #[must_use]
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Matches requests containing the given header value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name or value is invalid.
+    #[must_use]
+    pub fn header<K, V>(mut self, name: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        HeaderValue: TryFrom<V>,
+        <HeaderName as TryFrom<K>>::Error: fmt::Debug,
+        <HeaderValue as TryFrom<V>>::Error: fmt::Debug,
+    {
+        let name = HeaderName::try_from(name).expect("header name should be valid");
+        let value = HeaderValue::try_from(value).expect("header value should be valid");
+        self.headers.push((name, value));
+        self
+    }
+
+    /// Matches requests with exactly the given body.
+    #[must_use]
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Matches requests satisfying the given predicate.
+    #[must_use]
+    pub fn matches<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Request<Bytes>) -> bool + Send + Sync + 'static,
+    {
+        self.matchers.push(Arc::new(predicate));
+        self
+    }
+
+    /// Responds to the matched requests with the given response.
+    ///
+    /// The response extensions are not preserved.
+    #[must_use]
+    pub fn respond_with(mut self, response: Response<Bytes>) -> Self {
+        let (parts, body) = response.into_parts();
+        self.response = Ok(CannedResponse {
+            status: parts.status,
+            version: parts.version,
+            headers: parts.headers,
+            body,
+        });
+        self
+    }
+
+    /// Fails the matched requests with the [`MockError::Failure`] error.
+    #[must_use]
+    pub fn fail_with(mut self, message: impl Into<String>) -> Self {
+        self.response = Err(message.into());
+        self
+    }
+
+    /// Sets the exact number of the expected calls, which is checked by the
+    /// [`MockClientService::verify`] method.
+    #[must_use]
+    pub fn expect(mut self, calls: usize) -> Self {
+        self.expected = Some(calls);
+        self
+    }
+
+    fn is_match(&self, request: &Request<Bytes>) -> bool {
+        self.method
+            .as_ref()
+            .map_or(true, |method| request.method() == method)
+            && self
+                .path
+                .as_ref()
+                .map_or(true, |path| request.uri().path() == path)
+            && self.headers.iter().all(|(name, value)| {
+                request
+                    .headers()
+                    .get_all(name)
+                    .iter()
+                    .any(|actual| actual == value)
+            })
+            && self
+                .body
+                .as_ref()
+                .map_or(true, |body| request.body() == body)
+            && self.matchers.iter().all(|matcher| matcher(request))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CannedResponse {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+/// Handle of the registered [`Mock`] to inspect its calls.
+#[derive(Debug, Clone)]
+pub struct MockHandle {
+    calls: Arc<AtomicUsize>,
+}
+
+impl MockHandle {
+    /// Returns the number of requests matched by the mock.
+    #[must_use]
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::Acquire)
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    mocks: Vec<(Mock, Arc<AtomicUsize>)>,
+    requests: Vec<Request<Bytes>>,
+}
+
+/// In-memory HTTP client, which responds with the canned responses.
+///
+/// The `B` type parameter is the accepted request body type. The clones of this service
+/// share the registered mocks and recorded requests.
+///
+/// See the [module docs](self) for more details.
+pub struct MockClientService<B = Full<Bytes>> {
+    state: Arc<Mutex<State>>,
+    _body: PhantomData<fn(B)>,
+}
+
+impl<B> fmt::Debug for MockClientService<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockClientService")
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+impl<B> Clone for MockClientService<B> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            _body: PhantomData,
+        }
+    }
+}
+
+impl<B> Default for MockClientService<B> {
+    fn default() -> Self {
+        Self {
+            state: Arc::default(),
+            _body: PhantomData,
+        }
+    }
+}
+
+impl MockClientService {
+    /// Creates a new service without registered mocks, which accepts the [`Full<Bytes>`]
+    /// request bodies.
+    ///
+    /// Use the [`Default`] implementation to create a service with the other request body
+    /// type, for example, `MockClientService::<reqwest::Body>::default()`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B> MockClientService<B> {
+    /// Registers the given mock and returns its handle.
+    pub fn register(&self, mock: Mock) -> MockHandle {
+        let calls = Arc::new(AtomicUsize::new(0));
+        self.lock().mocks.push((mock, calls.clone()));
+        MockHandle { calls }
+    }
+
+    /// Returns the copies of all received requests in the order they were received.
+    ///
+    /// The request extensions are not preserved.
+    #[must_use]
+    pub fn requests(&self) -> Vec<Request<Bytes>> {
+        self.lock()
+            .requests
+            .iter()
+            .map(|request| {
+                let mut copy = Request::new(request.body().clone());
+                *copy.method_mut() = request.method().clone();
+                *copy.uri_mut() = request.uri().clone();
+                *copy.version_mut() = request.version();
+                *copy.headers_mut() = request.headers().clone();
+                copy
+            })
+            .collect()
+    }
+
+    /// Checks that the mocks were called the [expected](Mock::expect) number of times.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any expectation is not met.
+    pub fn verify(&self) {
+        let state = self.lock();
+        let failures = state
+            .mocks
+            .iter()
+            .filter_map(|(mock, calls)| {
+                let calls = calls.load(Ordering::Acquire);
+                let expected = mock.expected?;
+                (calls != expected)
+                    .then(|| format!("{mock:?} expected {expected} calls, got {calls}"))
+            })
+            .collect::<Vec<_>>();
+        assert!(
+            failures.is_empty(),
+            "mock expectations are not met:\n{}",
+            failures.join("\n")
+        );
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<B> Service<Request<B>> for MockClientService<B>
+where
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = MockError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let state = self.state.clone();
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let body = body
+                .collect()
+                .await
+                .map_err(|error| MockError::Body(error.into()))?
+                .to_bytes();
+            let request = Request::from_parts(parts, body);
+
+            let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+            let response = state
+                .mocks
+                .iter()
+                .find(|(mock, _)| mock.is_match(&request))
+                .map(|(mock, calls)| {
+                    calls.fetch_add(1, Ordering::AcqRel);
+                    mock.response.clone()
+                });
+            let unmatched = MockError::Unmatched {
+                method: request.method().clone(),
+                uri: request.uri().clone(),
+            };
+            state.requests.push(request);
+
+            let canned = response.ok_or(unmatched)?.map_err(MockError::Failure)?;
+            let mut response = Response::new(Full::new(canned.body));
+            *response.status_mut() = canned.status;
+            *response.version_mut() = canned.version;
+            *response.headers_mut() = canned.headers;
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use http::{header::CONTENT_TYPE, Method, StatusCode};
+    use http_body_util::Full;
+    use tower::ServiceBuilder;
+    use tower_http::ServiceBuilderExt as _;
+
+    use super::{Mock, MockClientService, MockError};
+    use crate::{ResponseExt as _, ServiceExt as _};
+
+    #[tokio::test]
+    async fn test_mock_client_service() -> anyhow::Result<()> {
+        let mock = MockClientService::new();
+        let created = mock.register(
+            Mock::new()
+                .method(Method::POST)
+                .path("/items")
+                .header(CONTENT_TYPE, "text/plain")
+                .body("payload")
+                .respond_with(
+                    http::Response::builder()
+                        .status(StatusCode::CREATED)
+                        .body("created".into())?,
+                )
+                .expect(1),
+        );
+        mock.register(Mock::new().path("/broken").fail_with("connection reset"));
+
+        let mut client = ServiceBuilder::new()
+            .override_request_header(
+                http::header::USER_AGENT,
+                http::HeaderValue::from_static("mock"),
+            )
+            .service(mock.clone());
+
+        let response = client
+            .post("http://localhost/items")
+            .header(CONTENT_TYPE, "text/plain")
+            .body::<Full<Bytes>>("payload")
+            .send()?
+            .await?;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.utf8().await?, "created");
+        assert_eq!(created.calls(), 1);
+
+        let error = client
+            .get("http://localhost/broken")
+            .send()?
+            .await
+            .unwrap_err();
+        assert!(matches!(error, MockError::Failure(message) if message == "connection reset"));
+
+        // The body doesn't match.
+        let error = client
+            .post("http://localhost/items")
+            .header(CONTENT_TYPE, "text/plain")
+            .body::<Full<Bytes>>("other")
+            .send()?
+            .await
+            .unwrap_err();
+        assert!(matches!(error, MockError::Unmatched { method, .. } if method == Method::POST));
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].headers()["user-agent"], "mock");
+        assert_eq!(requests[2].body(), "other");
+
+        mock.verify();
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "mock expectations are not met")]
+    fn test_mock_client_service_verify() {
+        let mock = MockClientService::<()>::default();
+        mock.register(Mock::new().expect(1));
+        mock.verify();
+    }
+}
@@ -0,0 +1,14 @@
+//! Helper for generating random numbers without depending on a random number generator.
+
+use std::hash::{BuildHasher, Hasher};
+
+/// Returns a random number.
+///
+/// Each `RandomState` is seeded with a random key, so the numbers are unpredictable enough
+/// for the jitter, boundaries and nonces. This is not a cryptographically secure generator,
+/// the values must not be used as secrets.
+pub(crate) fn random_u64() -> u64 {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    hasher.finish()
+}
@@ -0,0 +1,219 @@
+//! Middleware that rejects oversized request bodies before sending them.
+//!
+//! Unlike the [`tower-http`] middleware with the same name, which limits incoming bodies
+//! on the server side, this one is intended for the outbound requests. It is useful when
+//! forwarding user-generated bodies to the upstream servers.
+//!
+//! # Example
+//!
+//! ```
+//! use tower::ServiceBuilder;
+//! use tower_http_client::request_body_limit::RequestBodyLimitLayer;
+//! use tower_reqwest::HttpClientLayer;
+//!
+//! let client = ServiceBuilder::new()
+//!     // Reject requests with bodies larger than 1 MiB.
+//!     .layer(RequestBodyLimitLayer::new(1024 * 1024))
+//!     .layer(HttpClientLayer)
+//!     .service(reqwest::Client::new());
+//! ```
+//!
+//! [`tower-http`]: https://docs.rs/tower-http/latest/tower_http/limit/index.html
+
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use http::header::CONTENT_LENGTH;
+use http_body::Body;
+use http_body_util::{BodyExt as _, LengthLimitError, Limited};
+use thiserror::Error;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::BoxError;
+
+/// The request body exceeds the configured limit.
+#[derive(Debug, Error)]
+#[error("request body is larger than the limit of {limit} bytes")]
+pub struct PayloadTooLarge {
+    /// The maximum allowed body length in bytes.
+    pub limit: u64,
+}
+
+/// Layer that applies the [`RequestBodyLimit`] middleware.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestBodyLimitLayer {
+    limit: u64,
+}
+
+impl RequestBodyLimitLayer {
+    /// Creates a new layer with the given maximum body length in bytes.
+    #[must_use]
+    pub const fn new(limit: u64) -> Self {
+        Self { limit }
+    }
+}
+
+impl<S> Layer<S> for RequestBodyLimitLayer {
+    type Service = RequestBodyLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestBodyLimit::new(inner, self.limit)
+    }
+}
+
+/// Middleware that rejects requests with bodies exceeding the limit.
+///
+/// The request is checked before contacting the server:
+///
+/// - If the `Content-Length` header or the body size hint exceeds the limit, the
+///   [`PayloadTooLarge`] error is returned immediately.
+/// - If the body length is unknown in advance, the body is buffered up to the limit and
+///   sent as a single chunk, otherwise the [`PayloadTooLarge`] error is returned.
+///
+/// The buffered bytes are converted back into the request body type via the [`From<Bytes>`]
+/// implementation, so this middleware doesn't change the request body type.
+#[derive(Debug, Clone)]
+pub struct RequestBodyLimit<S> {
+    inner: S,
+    limit: u64,
+}
+
+impl<S> RequestBodyLimit<S> {
+    /// Creates a new middleware with the given maximum body length in bytes.
+    pub const fn new(inner: S, limit: u64) -> Self {
+        Self { inner, limit }
+    }
+}
+
+impl<S, ReqBody, RespBody> Service<http::Request<ReqBody>> for RequestBodyLimit<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<RespBody>>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+    ReqBody: Body + From<Bytes> + Send + 'static,
+    ReqBody::Data: Send,
+    ReqBody::Error: Into<BoxError>,
+    RespBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        // Take the service that was ready.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let limit = self.limit;
+
+        let content_length = request
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        let size_hint = request.body().size_hint();
+
+        if content_length.is_some_and(|length| length > limit) || size_hint.lower() > limit {
+            return Box::pin(std::future::ready(Err(PayloadTooLarge { limit }.into())));
+        }
+        if size_hint.upper().is_some_and(|upper| upper <= limit) {
+            return Box::pin(async move { inner.call(request).await.map_err(Into::into) });
+        }
+
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let body = buffer_body(body, limit).await?;
+            inner
+                .call(http::Request::from_parts(parts, body))
+                .await
+                .map_err(Into::into)
+        })
+    }
+}
+
+/// Reads the whole body into memory, if it doesn't exceed the limit.
+async fn buffer_body<B>(body: B, limit: u64) -> Result<B, BoxError>
+where
+    B: Body + From<Bytes>,
+    B::Error: Into<BoxError>,
+{
+    // If the limit doesn't fit into the memory, the body cannot be buffered anyway.
+    let max = usize::try_from(limit).unwrap_or(usize::MAX);
+    match Limited::new(body, max).collect().await {
+        Ok(collected) => Ok(B::from(collected.to_bytes())),
+        Err(err) if err.is::<LengthLimitError>() => Err(PayloadTooLarge { limit }.into()),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use bytes::Bytes;
+    use futures_util::stream;
+    use http_body_util::BodyExt as _;
+    use tower::{service_fn, ServiceExt as _};
+
+    use super::{PayloadTooLarge, RequestBodyLimit};
+
+    fn streaming_body(chunks: &'static [&'static [u8]]) -> reqwest::Body {
+        let chunks = chunks
+            .iter()
+            .map(|chunk| Ok::<_, Infallible>(Bytes::from(*chunk)));
+        reqwest::Body::wrap_stream(stream::iter(chunks))
+    }
+
+    async fn echo(
+        request: http::Request<reqwest::Body>,
+    ) -> Result<http::Response<Bytes>, reqwest::Error> {
+        let body = request.into_body().collect().await?.to_bytes();
+        Ok(http::Response::new(body))
+    }
+
+    #[tokio::test]
+    async fn test_request_body_limit_known_length() {
+        let service = RequestBodyLimit::new(service_fn(echo), 5);
+
+        let request = http::Request::new(reqwest::Body::from("Hello"));
+        let response = service.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.into_body(), "Hello");
+
+        let request = http::Request::new(reqwest::Body::from("Hello!"));
+        let error = service.oneshot(request).await.unwrap_err();
+        assert!(error.is::<PayloadTooLarge>());
+    }
+
+    #[tokio::test]
+    async fn test_request_body_limit_content_length() {
+        let service = RequestBodyLimit::new(service_fn(echo), 5);
+
+        let request = http::Request::builder()
+            .header(http::header::CONTENT_LENGTH, "100")
+            .body(streaming_body(&[b"Hello"]))
+            .unwrap();
+        let error = service.oneshot(request).await.unwrap_err();
+        assert!(error.is::<PayloadTooLarge>());
+    }
+
+    #[tokio::test]
+    async fn test_request_body_limit_streaming() {
+        let service = RequestBodyLimit::new(service_fn(echo), 5);
+
+        let request = http::Request::new(streaming_body(&[b"Hel", b"lo"]));
+        let response = service.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.into_body(), "Hello");
+
+        let request = http::Request::new(streaming_body(&[b"Hel", b"lo", b"!"]));
+        let error = service.oneshot(request).await.unwrap_err();
+        assert!(error.is::<PayloadTooLarge>());
+    }
+}
@@ -0,0 +1,791 @@
+//! Middleware that retries failed requests with exponential backoff.
+//!
+//! Unlike the generic [`tower::retry`] middleware, this one is tailored to HTTP clients:
+//!
+//! - By default, only the idempotent requests are retried, if they fail or the server
+//!   responds with the `408`, `429` or `5xx` status. The behavior can be changed by
+//!   the custom [`RetryPredicate`].
+//! - The delay between attempts grows exponentially with a random jitter.
+//! - The `Retry-After` response header is honored.
+//! - The total number of retries can be bounded relative to the number of requests by
+//!   the shared [`RetryBudget`], so a surge of failures doesn't amplify load on an already
+//!   struggling server.
+//! - Each retry can be observed by the [`on_retry`](RetryLayer::on_retry) hook, for example,
+//!   to log it or to record metrics.
+//! - The time source can be replaced by the custom [`Clock`], so the middleware can be tested
+//!   and benchmarked without real waiting.
+//!
+//! # Request body
+//!
+//! Each attempt needs a fresh copy of the request, so the request body must implement the
+//! [`TryCloneBody`] trait. Keep the request bodies in memory, for example, as [`Bytes`] or
+//! [`Full`], to make them retriable. Requests with bodies that cannot be cloned, like
+//! streaming ones, are sent only once.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use tower::ServiceBuilder;
+//! use tower_http_client::retry::RetryLayer;
+//! use tower_reqwest::HttpClientLayer;
+//!
+//! let client = ServiceBuilder::new()
+//!     .layer(
+//!         RetryLayer::new()
+//!             .max_attempts(5)
+//!             .backoff(Duration::from_millis(50), Duration::from_secs(5)),
+//!     )
+//!     .layer(HttpClientLayer)
+//!     .service(reqwest::Client::new());
+//! ```
+//!
+//! [`tower::retry`]: https://docs.rs/tower/latest/tower/retry/index.html
+//! [`TryCloneBody`]: crate::client::TryCloneBody
+//! [`Bytes`]: bytes::Bytes
+//! [`Full`]: http_body_util::Full
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::future::BoxFuture;
+use http::{HeaderMap, Method, StatusCode};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{
+    client::{rate_limit::retry_after, try_clone::try_clone_request, TryCloneBody},
+    clock::{Clock, SharedClock, TokioClock},
+    random::random_u64,
+};
+
+/// Decides whether the failed request should be retried.
+///
+/// This trait is implemented for the closures with the matching signature.
+pub trait RetryPredicate<ReqBody, RespBody, E> {
+    /// Returns `true` if the request should be retried after the given result.
+    fn should_retry(
+        &self,
+        request: &http::Request<ReqBody>,
+        result: Result<&http::Response<RespBody>, &E>,
+    ) -> bool;
+}
+
+impl<F, ReqBody, RespBody, E> RetryPredicate<ReqBody, RespBody, E> for F
+where
+    F: Fn(&http::Request<ReqBody>, Result<&http::Response<RespBody>, &E>) -> bool,
+{
+    fn should_retry(
+        &self,
+        request: &http::Request<ReqBody>,
+        result: Result<&http::Response<RespBody>, &E>,
+    ) -> bool {
+        self(request, result)
+    }
+}
+
+/// The default [`RetryPredicate`].
+///
+/// Retries the requests with idempotent methods, if they fail or the server responds with
+/// the `408 Request Timeout`, `429 Too Many Requests` or `5xx` status.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultRetryPredicate;
+
+impl<ReqBody, RespBody, E> RetryPredicate<ReqBody, RespBody, E> for DefaultRetryPredicate {
+    fn should_retry(
+        &self,
+        request: &http::Request<ReqBody>,
+        result: Result<&http::Response<RespBody>, &E>,
+    ) -> bool {
+        is_idempotent(request.method())
+            && result.map_or(true, |response| is_retriable_status(response.status()))
+    }
+}
+
+/// The cost of a single retry in the budget units.
+const RETRY_COST: u64 = 100;
+
+/// A budget which bounds the total number of retries relative to the number of requests.
+///
+/// The budget works like a token bucket: each sent request deposits the configured percent
+/// of a retry into the budget, and each retry withdraws a whole one. If the budget is
+/// exhausted, the failed requests are not retried until new requests replenish it.
+///
+/// The budget is shared between its clones, so the same budget can be used by several
+/// retry layers.
+///
+/// # Example
+///
+/// ```
+/// use tower_http_client::retry::{RetryBudget, RetryLayer};
+///
+/// // Allow 20% extra requests for retries.
+/// let layer = RetryLayer::new().budget(RetryBudget::new(20));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    state: Arc<BudgetState>,
+}
+
+#[derive(Debug)]
+struct BudgetState {
+    deposit: u64,
+    capacity: u64,
+    balance: AtomicU64,
+    requests: AtomicU64,
+    retries: AtomicU64,
+    exhausted: AtomicU64,
+}
+
+/// A snapshot of the [`RetryBudget`] counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryBudgetStats {
+    /// The number of requests which deposited into the budget.
+    pub requests: u64,
+    /// The number of retries allowed by the budget.
+    pub retries: u64,
+    /// The number of retries denied because the budget was exhausted.
+    pub exhausted: u64,
+    /// The number of retries currently available.
+    pub available: u64,
+}
+
+impl RetryBudget {
+    /// Creates a new budget which allows the given percent of extra requests for retries.
+    ///
+    /// The budget starts with 10 retries available and accumulates up to 100 retries.
+    #[must_use]
+    pub fn new(retry_percent: u32) -> Self {
+        Self::with_capacity(retry_percent, 10, 100)
+    }
+
+    /// Creates a new budget with the given number of initially available retries and
+    /// the maximum number of retries which can be accumulated.
+    #[must_use]
+    pub fn with_capacity(retry_percent: u32, initial_retries: u32, max_retries: u32) -> Self {
+        let capacity = u64::from(max_retries.max(initial_retries)) * RETRY_COST;
+        Self {
+            state: Arc::new(BudgetState {
+                deposit: u64::from(retry_percent),
+                capacity,
+                balance: AtomicU64::new(u64::from(initial_retries) * RETRY_COST),
+                requests: AtomicU64::new(0),
+                retries: AtomicU64::new(0),
+                exhausted: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Returns the current counters of this budget.
+    #[must_use]
+    pub fn stats(&self) -> RetryBudgetStats {
+        RetryBudgetStats {
+            requests: self.state.requests.load(Ordering::Relaxed),
+            retries: self.state.retries.load(Ordering::Relaxed),
+            exhausted: self.state.exhausted.load(Ordering::Relaxed),
+            available: self.state.balance.load(Ordering::Relaxed) / RETRY_COST,
+        }
+    }
+
+    // Deposits the share of a new request.
+    fn deposit(&self) {
+        self.state.requests.fetch_add(1, Ordering::Relaxed);
+        let _ = self
+            .state
+            .balance
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |balance| {
+                Some(
+                    balance
+                        .saturating_add(self.state.deposit)
+                        .min(self.state.capacity),
+                )
+            });
+    }
+
+    // Withdraws a single retry, returns `false` if the budget is exhausted.
+    fn withdraw(&self) -> bool {
+        let withdrawn = self
+            .state
+            .balance
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |balance| {
+                balance.checked_sub(RETRY_COST)
+            })
+            .is_ok();
+        if withdrawn {
+            self.state.retries.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.state.exhausted.fetch_add(1, Ordering::Relaxed);
+        }
+        withdrawn
+    }
+}
+
+/// Information about the retry passed to the [`on_retry`](RetryLayer::on_retry) hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RetryAttempt {
+    /// The number of the failed attempt, starting from 1.
+    pub attempt: u32,
+    /// The status of the failed attempt response, or `None` if the request failed.
+    pub status: Option<StatusCode>,
+    /// The delay before the next attempt.
+    pub delay: Duration,
+}
+
+// The hook invoked before each retry.
+#[derive(Clone)]
+struct OnRetry(Arc<dyn Fn(&RetryAttempt) + Send + Sync>);
+
+impl fmt::Debug for OnRetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnRetry").finish_non_exhaustive()
+    }
+}
+
+/// Layer that applies the [`Retry`] middleware.
+#[derive(Debug, Clone)]
+pub struct RetryLayer<P = DefaultRetryPredicate> {
+    config: RetryConfig,
+    budget: Option<RetryBudget>,
+    hook: Option<OnRetry>,
+    clock: Option<SharedClock>,
+    predicate: P,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RetryConfig {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    honor_retry_after: bool,
+}
+
+impl RetryLayer {
+    /// Creates a new layer with the default settings.
+    ///
+    /// By default, a request is sent at most 3 times, the backoff starts from 100ms and
+    /// is capped at 10s, and the `Retry-After` header is honored.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            config: RetryConfig::DEFAULT,
+            budget: None,
+            hook: None,
+            clock: None,
+            predicate: DefaultRetryPredicate,
+        }
+    }
+}
+
+impl Default for RetryLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> RetryLayer<P> {
+    /// Sets the maximum number of attempts including the first one.
+    #[must_use]
+    pub const fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.config.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the delay before the first retry and the maximum delay between attempts.
+    ///
+    /// The delay is doubled after each attempt and a random jitter of up to a half of the
+    /// delay is subtracted from it.
+    #[must_use]
+    pub const fn backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.config.base_delay = base_delay;
+        self.config.max_delay = max_delay;
+        self
+    }
+
+    /// Sets whether the `Retry-After` response header should be honored.
+    ///
+    /// If enabled, the delay specified by the server is used instead of the backoff one.
+    /// If the specified delay exceeds the maximum delay, the request is not retried.
+    #[must_use]
+    pub const fn honor_retry_after(mut self, honor: bool) -> Self {
+        self.config.honor_retry_after = honor;
+        self
+    }
+
+    /// Sets the budget which bounds the total number of retries.
+    ///
+    /// By default, the number of retries is limited only per request.
+    #[must_use]
+    pub fn budget(mut self, budget: RetryBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Sets the hook which is invoked before each retry.
+    ///
+    /// The hook is called after the decision to retry is made, right before waiting for
+    /// the backoff delay, so it is not called for the final attempt.
+    #[must_use]
+    pub fn on_retry<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&RetryAttempt) + Send + Sync + 'static,
+    {
+        self.hook = Some(OnRetry(Arc::new(hook)));
+        self
+    }
+
+    /// Sets the clock which is used to wait between attempts.
+    ///
+    /// By default, the [`TokioClock`] is used.
+    #[must_use]
+    pub fn clock(mut self, clock: impl Clock) -> Self {
+        self.clock = Some(SharedClock::new(clock));
+        self
+    }
+
+    /// Sets the predicate which decides whether the failed request should be retried.
+    #[must_use]
+    pub fn retry_if<Q>(self, predicate: Q) -> RetryLayer<Q> {
+        RetryLayer {
+            config: self.config,
+            budget: self.budget,
+            hook: self.hook,
+            clock: self.clock,
+            predicate,
+        }
+    }
+}
+
+impl<S, P: Clone> Layer<S> for RetryLayer<P> {
+    type Service = Retry<S, P>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Retry {
+            inner,
+            config: self.config,
+            budget: self.budget.clone(),
+            hook: self.hook.clone(),
+            clock: self.clock.clone(),
+            predicate: self.predicate.clone(),
+        }
+    }
+}
+
+/// Middleware that retries failed requests with exponential backoff.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct Retry<S, P = DefaultRetryPredicate> {
+    inner: S,
+    config: RetryConfig,
+    budget: Option<RetryBudget>,
+    hook: Option<OnRetry>,
+    clock: Option<SharedClock>,
+    predicate: P,
+}
+
+impl<S> Retry<S> {
+    /// Creates a new middleware with the default settings.
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            config: RetryConfig::DEFAULT,
+            budget: None,
+            hook: None,
+            clock: None,
+            predicate: DefaultRetryPredicate,
+        }
+    }
+}
+
+impl<S, P, ReqBody, RespBody> Service<http::Request<ReqBody>> for Retry<S, P>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<RespBody>>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    P: RetryPredicate<ReqBody, RespBody, S::Error> + Clone + Send + 'static,
+    ReqBody: TryCloneBody + Send + 'static,
+    RespBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<ReqBody>) -> Self::Future {
+        // Take the service that was ready.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let config = self.config;
+        let budget = self.budget.clone();
+        let hook = self.hook.clone();
+        let clock = self.clock.clone();
+        let predicate = self.predicate.clone();
+
+        Box::pin(async move {
+            let clock = clock
+                .as_ref()
+                .map_or(&TokioClock as &dyn Clock, |clock| &*clock.0);
+            if let Some(budget) = &budget {
+                budget.deposit();
+            }
+
+            let mut attempt = 1;
+            loop {
+                let replay = if attempt < config.max_attempts {
+                    try_clone_request(&request)
+                } else {
+                    None
+                };
+
+                let result = inner.call(request).await;
+                let Some(replay) = replay else {
+                    return result;
+                };
+                if !predicate.should_retry(&replay, result.as_ref()) {
+                    return result;
+                }
+                let headers = result.as_ref().ok().map(http::Response::headers);
+                let Some(delay) = config.delay(attempt, headers, clock) else {
+                    return result;
+                };
+                if budget.as_ref().is_some_and(|budget| !budget.withdraw()) {
+                    return result;
+                }
+                if let Some(OnRetry(hook)) = &hook {
+                    hook(&RetryAttempt {
+                        attempt,
+                        status: result.as_ref().ok().map(http::Response::status),
+                        delay,
+                    });
+                }
+                drop(result);
+
+                clock.sleep(delay).await;
+                futures_util::future::poll_fn(|cx| inner.poll_ready(cx)).await?;
+                request = replay;
+                attempt += 1;
+            }
+        })
+    }
+}
+
+impl RetryConfig {
+    const DEFAULT: Self = Self {
+        max_attempts: 3,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(10),
+        honor_retry_after: true,
+    };
+
+    // Returns the delay before the next attempt, or `None` if the server asks to wait
+    // longer than allowed.
+    fn delay(
+        &self,
+        attempt: u32,
+        headers: Option<&HeaderMap>,
+        clock: &dyn Clock,
+    ) -> Option<Duration> {
+        if self.honor_retry_after {
+            if let Some(delay) = headers.and_then(|headers| retry_after(headers, clock.now())) {
+                return (delay <= self.max_delay).then_some(delay);
+            }
+        }
+
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        Some(delay.saturating_sub(jitter(delay / 2)))
+    }
+}
+
+// Returns a random duration in the range `0..=max`.
+fn jitter(max: Duration) -> Duration {
+    let max_nanos = u64::try_from(max.as_nanos()).unwrap_or(u64::MAX);
+    Duration::from_nanos(random_u64() % max_nanos.saturating_add(1))
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE
+    )
+}
+
+fn is_retriable_status(status: StatusCode) -> bool {
+    status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status.is_server_error()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::{Duration, Instant, SystemTime},
+    };
+
+    use bytes::Bytes;
+    use futures_util::future::BoxFuture;
+    use http::{header::RETRY_AFTER, Method, StatusCode};
+    use tower::{service_fn, ServiceExt as _};
+    use tower_layer::Layer as _;
+
+    use super::{RetryAttempt, RetryBudget, RetryBudgetStats, RetryLayer};
+    use crate::clock::Clock;
+
+    // Clock which records the delays instead of waiting.
+    #[derive(Clone, Default)]
+    struct ManualClock {
+        sleeps: Arc<Mutex<Vec<Duration>>>,
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> SystemTime {
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000)
+        }
+
+        fn instant(&self) -> Instant {
+            Instant::now()
+        }
+
+        fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+            self.sleeps.lock().unwrap().push(duration);
+            Box::pin(std::future::ready(()))
+        }
+    }
+
+    // Returns a service which responds with the given statuses in order.
+    fn statuses(
+        statuses: &'static [StatusCode],
+        calls: Arc<AtomicUsize>,
+    ) -> impl tower_service::Service<
+        http::Request<Bytes>,
+        Response = http::Response<()>,
+        Error = Infallible,
+        Future = impl Send,
+    > + Clone {
+        service_fn(move |_request: http::Request<Bytes>| {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            let status = statuses[call.min(statuses.len() - 1)];
+            async move {
+                let mut response = http::Response::new(());
+                *response.status_mut() = status;
+                Ok(response)
+            }
+        })
+    }
+
+    fn layer() -> RetryLayer {
+        RetryLayer::new().backoff(Duration::from_millis(1), Duration::from_millis(10))
+    }
+
+    #[tokio::test]
+    async fn test_retry_idempotent_request() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service = layer().layer(statuses(
+            &[StatusCode::SERVICE_UNAVAILABLE, StatusCode::OK],
+            calls.clone(),
+        ));
+
+        let response = service.oneshot(http::Request::new(Bytes::new())).await;
+        assert_eq!(response.unwrap().status(), StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_max_attempts() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service = layer()
+            .max_attempts(4)
+            .layer(statuses(&[StatusCode::BAD_GATEWAY], calls.clone()));
+
+        let response = service.oneshot(http::Request::new(Bytes::new())).await;
+        assert_eq!(response.unwrap().status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_retry_non_idempotent_request() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service = layer().layer(statuses(&[StatusCode::BAD_GATEWAY], calls.clone()));
+
+        let request = http::Request::builder()
+            .method(Method::POST)
+            .body(Bytes::new())
+            .unwrap();
+        let response = service.oneshot(request).await;
+        assert_eq!(response.unwrap().status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_custom_predicate() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service = layer()
+            .retry_if(
+                |_: &http::Request<Bytes>, result: Result<&http::Response<()>, &Infallible>| {
+                    result.is_ok_and(|response| response.status() == StatusCode::CONFLICT)
+                },
+            )
+            .layer(statuses(
+                &[StatusCode::CONFLICT, StatusCode::CREATED],
+                calls.clone(),
+            ));
+
+        let request = http::Request::builder()
+            .method(Method::POST)
+            .body(Bytes::new())
+            .unwrap();
+        let response = service.oneshot(request).await;
+        assert_eq!(response.unwrap().status(), StatusCode::CREATED);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_too_long_retry_after() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service = layer().layer(service_fn({
+            let calls = calls.clone();
+            move |_request: http::Request<Bytes>| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async {
+                    let response = http::Response::builder()
+                        .status(StatusCode::SERVICE_UNAVAILABLE)
+                        .header(RETRY_AFTER, "3600")
+                        .body(())
+                        .unwrap();
+                    Ok::<_, Infallible>(response)
+                }
+            }
+        }));
+
+        let response = service.oneshot(http::Request::new(Bytes::new())).await;
+        assert_eq!(response.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_retry_hook() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+        let service = layer()
+            .on_retry({
+                let attempts = attempts.clone();
+                move |attempt: &RetryAttempt| attempts.lock().unwrap().push(*attempt)
+            })
+            .max_attempts(3)
+            .layer(statuses(&[StatusCode::BAD_GATEWAY], calls.clone()));
+
+        let response = service.oneshot(http::Request::new(Bytes::new())).await;
+        assert_eq!(response.unwrap().status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // The hook is not called for the final attempt.
+        let attempts = attempts.lock().unwrap();
+        assert_eq!(
+            attempts.iter().map(|a| a.attempt).collect::<Vec<_>>(),
+            [1, 2]
+        );
+        for attempt in attempts.iter() {
+            assert_eq!(attempt.status, Some(StatusCode::BAD_GATEWAY));
+            assert!(attempt.delay <= Duration::from_millis(10));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_custom_clock() {
+        let clock = ManualClock::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service = RetryLayer::new()
+            .clock(clock.clone())
+            .max_attempts(3)
+            .backoff(Duration::from_secs(3600), Duration::from_secs(3600))
+            .layer(service_fn({
+                let calls = calls.clone();
+                move |_request: http::Request<Bytes>| {
+                    let call = calls.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        let mut response = http::Response::builder();
+                        if call == 0 {
+                            // 30 seconds later than the clock time.
+                            let date = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_030);
+                            response = response
+                                .status(StatusCode::SERVICE_UNAVAILABLE)
+                                .header(RETRY_AFTER, httpdate::fmt_http_date(date));
+                        } else {
+                            response = response.status(StatusCode::BAD_GATEWAY);
+                        }
+                        Ok::<_, Infallible>(response.body(()).unwrap())
+                    }
+                }
+            }));
+
+        // The hour-long backoff is not actually awaited.
+        let response = service.oneshot(http::Request::new(Bytes::new())).await;
+        assert_eq!(response.unwrap().status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let sleeps = clock.sleeps.lock().unwrap();
+        assert_eq!(sleeps[0], Duration::from_secs(30));
+        assert!(sleeps[1] >= Duration::from_secs(1800));
+        assert_eq!(sleeps.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        // Each request adds a half of a retry, one retry is available initially.
+        let budget = RetryBudget::with_capacity(50, 1, 10);
+        let service = layer()
+            .max_attempts(10)
+            .budget(budget.clone())
+            .layer(statuses(&[StatusCode::BAD_GATEWAY], calls.clone()));
+
+        // The first request uses the initial retry and the deposited half.
+        let response = service
+            .clone()
+            .oneshot(http::Request::new(Bytes::new()))
+            .await;
+        assert_eq!(response.unwrap().status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // The second request completes the deposited retry.
+        service
+            .clone()
+            .oneshot(http::Request::new(Bytes::new()))
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        assert_eq!(
+            budget.stats(),
+            RetryBudgetStats {
+                requests: 2,
+                retries: 2,
+                exhausted: 2,
+                available: 0,
+            }
+        );
+    }
+}
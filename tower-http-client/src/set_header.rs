@@ -0,0 +1,522 @@
+//! Middleware that sets request headers with asynchronously produced values.
+//!
+//! The [`tower-http`] `SetRequestHeader` middleware computes header values synchronously,
+//! which doesn't fit values coming from the async sources, like an OAuth token provider
+//! refreshing an expiring token. The [`AsyncSetRequestHeader`] middleware awaits the
+//! [`AsyncMakeHeaderValue`] future before forwarding the request to the inner service:
+//!
+//! - If the future resolves to `None`, the header is left unset.
+//! - If the future fails, the error is returned as the service error and the request is not
+//!   sent.
+//!
+//! # Example
+//!
+//! ```
+//! use http::{header::AUTHORIZATION, HeaderValue};
+//! use tower::ServiceBuilder;
+//! use tower_http_client::set_header::AsyncSetRequestHeaderLayer;
+//! use tower_reqwest::HttpClientLayer;
+//!
+//! async fn fetch_token() -> anyhow::Result<String> {
+//!     Ok("secret-token".to_owned())
+//! }
+//!
+//! let client = ServiceBuilder::new()
+//!     .layer(AsyncSetRequestHeaderLayer::overriding(
+//!         AUTHORIZATION,
+//!         |_request: &http::Request<reqwest::Body>| async {
+//!             let token = fetch_token().await?;
+//!             let value = HeaderValue::try_from(format!("Bearer {token}"))?;
+//!             Ok::<_, anyhow::Error>(Some(value))
+//!         },
+//!     ))
+//!     .layer(HttpClientLayer)
+//!     .service(reqwest::Client::new());
+//! ```
+//!
+//! The [`SetRequestHeaderFromExtension`] middleware derives a header value from a typed
+//! request extension instead, like a correlation id attached earlier in the stack. The
+//! header is left unset if the request has no such extension:
+//!
+//! ```
+//! use http::{HeaderName, HeaderValue};
+//! use tower::ServiceBuilder;
+//! use tower_http_client::set_header::SetRequestHeaderFromExtensionLayer;
+//! use tower_reqwest::HttpClientLayer;
+//!
+//! #[derive(Clone)]
+//! struct CorrelationId(u64);
+//!
+//! let client = ServiceBuilder::new()
+//!     .layer(SetRequestHeaderFromExtensionLayer::overriding(
+//!         HeaderName::from_static("x-correlation-id"),
+//!         |id: &CorrelationId| Some(HeaderValue::from(id.0)),
+//!     ))
+//!     .layer(HttpClientLayer)
+//!     .service(reqwest::Client::new());
+//! ```
+//!
+//! [`tower-http`]: https://docs.rs/tower-http/latest/tower_http/set_header/index.html
+
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    task::{Context, Poll},
+};
+
+use futures_util::future::BoxFuture;
+use http::{HeaderMap, HeaderName, HeaderValue, Request};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::BoxError;
+
+/// Trait for producing header values asynchronously.
+///
+/// It is implemented for closures taking a reference to the message and returning
+/// a future, which resolves to an optional header value or an error. The future cannot
+/// borrow the message, so the required message parts should be copied before it is
+/// created.
+pub trait AsyncMakeHeaderValue<T> {
+    /// The error produced by the future.
+    type Error;
+    /// The future resolving to the header value.
+    type Future: Future<Output = Result<Option<HeaderValue>, Self::Error>>;
+
+    /// Starts producing a header value for the given message.
+    fn make_header_value(&mut self, message: &T) -> Self::Future;
+}
+
+impl<F, Fut, T, E> AsyncMakeHeaderValue<T> for F
+where
+    F: FnMut(&T) -> Fut,
+    Fut: Future<Output = Result<Option<HeaderValue>, E>>,
+{
+    type Error = E;
+    type Future = Fut;
+
+    fn make_header_value(&mut self, message: &T) -> Self::Future {
+        self(message)
+    }
+}
+
+/// The way the header value is inserted into the headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InsertHeaderMode {
+    Override,
+    Append,
+    IfNotPresent,
+}
+
+impl InsertHeaderMode {
+    pub(crate) fn insert(self, headers: &mut HeaderMap, name: HeaderName, value: HeaderValue) {
+        match self {
+            Self::Override => {
+                headers.insert(name, value);
+            }
+            Self::Append => {
+                headers.append(name, value);
+            }
+            Self::IfNotPresent => {
+                headers.entry(name).or_insert(value);
+            }
+        }
+    }
+}
+
+/// Layer that applies the [`AsyncSetRequestHeader`] middleware.
+#[derive(Debug, Clone)]
+pub struct AsyncSetRequestHeaderLayer<M> {
+    name: HeaderName,
+    make: M,
+    mode: InsertHeaderMode,
+}
+
+impl<M> AsyncSetRequestHeaderLayer<M> {
+    /// Creates a new layer, which overrides the header if it is already present.
+    pub const fn overriding(name: HeaderName, make: M) -> Self {
+        Self::new(name, make, InsertHeaderMode::Override)
+    }
+
+    /// Creates a new layer, which appends the header value to the already present ones.
+    pub const fn appending(name: HeaderName, make: M) -> Self {
+        Self::new(name, make, InsertHeaderMode::Append)
+    }
+
+    /// Creates a new layer, which sets the header only if it is not present yet.
+    ///
+    /// The header value is not produced at all if the header is already present.
+    pub const fn if_not_present(name: HeaderName, make: M) -> Self {
+        Self::new(name, make, InsertHeaderMode::IfNotPresent)
+    }
+
+    const fn new(name: HeaderName, make: M, mode: InsertHeaderMode) -> Self {
+        Self { name, make, mode }
+    }
+}
+
+impl<S, M: Clone> Layer<S> for AsyncSetRequestHeaderLayer<M> {
+    type Service = AsyncSetRequestHeader<S, M>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AsyncSetRequestHeader {
+            inner,
+            name: self.name.clone(),
+            make: self.make.clone(),
+            mode: self.mode,
+        }
+    }
+}
+
+/// Middleware that sets a request header with an asynchronously produced value.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct AsyncSetRequestHeader<S, M> {
+    inner: S,
+    name: HeaderName,
+    make: M,
+    mode: InsertHeaderMode,
+}
+
+impl<S, M> AsyncSetRequestHeader<S, M> {
+    /// Creates a new middleware, which overrides the header if it is already present.
+    pub const fn overriding(inner: S, name: HeaderName, make: M) -> Self {
+        Self::new(inner, name, make, InsertHeaderMode::Override)
+    }
+
+    /// Creates a new middleware, which appends the header value to the already present ones.
+    pub const fn appending(inner: S, name: HeaderName, make: M) -> Self {
+        Self::new(inner, name, make, InsertHeaderMode::Append)
+    }
+
+    /// Creates a new middleware, which sets the header only if it is not present yet.
+    pub const fn if_not_present(inner: S, name: HeaderName, make: M) -> Self {
+        Self::new(inner, name, make, InsertHeaderMode::IfNotPresent)
+    }
+
+    const fn new(inner: S, name: HeaderName, make: M, mode: InsertHeaderMode) -> Self {
+        Self {
+            inner,
+            name,
+            make,
+            mode,
+        }
+    }
+}
+
+impl<S, M, ReqBody> Service<Request<ReqBody>> for AsyncSetRequestHeader<S, M>
+where
+    S: Service<Request<ReqBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+    M: AsyncMakeHeaderValue<Request<ReqBody>>,
+    M::Future: Send + 'static,
+    M::Error: Into<BoxError>,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        // Take the service that was ready.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let skip = self.mode == InsertHeaderMode::IfNotPresent
+            && request.headers().contains_key(&self.name);
+        let value = (!skip).then(|| self.make.make_header_value(&request));
+        let name = self.name.clone();
+        let mode = self.mode;
+
+        Box::pin(async move {
+            if let Some(value) = value {
+                if let Some(value) = value.await.map_err(Into::into)? {
+                    mode.insert(request.headers_mut(), name, value);
+                }
+            }
+            inner.call(request).await.map_err(Into::into)
+        })
+    }
+}
+
+/// Layer that applies the [`SetRequestHeaderFromExtension`] middleware.
+pub struct SetRequestHeaderFromExtensionLayer<T, F> {
+    name: HeaderName,
+    make: F,
+    mode: InsertHeaderMode,
+    _extension: PhantomData<fn() -> T>,
+}
+
+impl<T, F> SetRequestHeaderFromExtensionLayer<T, F>
+where
+    F: Fn(&T) -> Option<HeaderValue>,
+{
+    /// Creates a new layer, which overrides the header if it is already present.
+    pub const fn overriding(name: HeaderName, make: F) -> Self {
+        Self::new(name, make, InsertHeaderMode::Override)
+    }
+
+    /// Creates a new layer, which appends the header value to the already present ones.
+    pub const fn appending(name: HeaderName, make: F) -> Self {
+        Self::new(name, make, InsertHeaderMode::Append)
+    }
+
+    /// Creates a new layer, which sets the header only if it is not present yet.
+    pub const fn if_not_present(name: HeaderName, make: F) -> Self {
+        Self::new(name, make, InsertHeaderMode::IfNotPresent)
+    }
+
+    const fn new(name: HeaderName, make: F, mode: InsertHeaderMode) -> Self {
+        Self {
+            name,
+            make,
+            mode,
+            _extension: PhantomData,
+        }
+    }
+}
+
+impl<T, F: Clone> Clone for SetRequestHeaderFromExtensionLayer<T, F> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            make: self.make.clone(),
+            mode: self.mode,
+            _extension: PhantomData,
+        }
+    }
+}
+
+impl<T, F> fmt::Debug for SetRequestHeaderFromExtensionLayer<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SetRequestHeaderFromExtensionLayer")
+            .field("name", &self.name)
+            .field("mode", &self.mode)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, T, F: Clone> Layer<S> for SetRequestHeaderFromExtensionLayer<T, F> {
+    type Service = SetRequestHeaderFromExtension<S, T, F>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SetRequestHeaderFromExtension {
+            inner,
+            name: self.name.clone(),
+            make: self.make.clone(),
+            mode: self.mode,
+            _extension: PhantomData,
+        }
+    }
+}
+
+/// Middleware that sets a request header with a value derived from a request extension.
+///
+/// The `make` closure converts the extension of type `T` into the header value. The header
+/// is left unset if the request has no such extension or the closure returns `None`.
+///
+/// See the [module docs](self) for more details.
+pub struct SetRequestHeaderFromExtension<S, T, F> {
+    inner: S,
+    name: HeaderName,
+    make: F,
+    mode: InsertHeaderMode,
+    _extension: PhantomData<fn() -> T>,
+}
+
+impl<S, T, F> SetRequestHeaderFromExtension<S, T, F>
+where
+    F: Fn(&T) -> Option<HeaderValue>,
+{
+    /// Creates a new middleware, which overrides the header if it is already present.
+    pub const fn overriding(inner: S, name: HeaderName, make: F) -> Self {
+        Self::new(inner, name, make, InsertHeaderMode::Override)
+    }
+
+    /// Creates a new middleware, which appends the header value to the already present ones.
+    pub const fn appending(inner: S, name: HeaderName, make: F) -> Self {
+        Self::new(inner, name, make, InsertHeaderMode::Append)
+    }
+
+    /// Creates a new middleware, which sets the header only if it is not present yet.
+    pub const fn if_not_present(inner: S, name: HeaderName, make: F) -> Self {
+        Self::new(inner, name, make, InsertHeaderMode::IfNotPresent)
+    }
+
+    const fn new(inner: S, name: HeaderName, make: F, mode: InsertHeaderMode) -> Self {
+        Self {
+            inner,
+            name,
+            make,
+            mode,
+            _extension: PhantomData,
+        }
+    }
+}
+
+impl<S: Clone, T, F: Clone> Clone for SetRequestHeaderFromExtension<S, T, F> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            name: self.name.clone(),
+            make: self.make.clone(),
+            mode: self.mode,
+            _extension: PhantomData,
+        }
+    }
+}
+
+impl<S: fmt::Debug, T, F> fmt::Debug for SetRequestHeaderFromExtension<S, T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SetRequestHeaderFromExtension")
+            .field("inner", &self.inner)
+            .field("name", &self.name)
+            .field("mode", &self.mode)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, T, F, ReqBody> Service<Request<ReqBody>> for SetRequestHeaderFromExtension<S, T, F>
+where
+    S: Service<Request<ReqBody>>,
+    T: Send + Sync + 'static,
+    F: Fn(&T) -> Option<HeaderValue>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        let skip = self.mode == InsertHeaderMode::IfNotPresent
+            && request.headers().contains_key(&self.name);
+        if !skip {
+            if let Some(value) = request.extensions().get::<T>().and_then(&self.make) {
+                self.mode
+                    .insert(request.headers_mut(), self.name.clone(), value);
+            }
+        }
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use http::{header::AUTHORIZATION, HeaderValue, Request, Response};
+    use tower::{service_fn, ServiceExt as _};
+
+    use super::{AsyncSetRequestHeader, SetRequestHeaderFromExtension};
+
+    #[tokio::test]
+    async fn test_async_set_request_header() {
+        let inner = service_fn(|request: Request<()>| async move {
+            let values = request
+                .headers()
+                .get_all(AUTHORIZATION)
+                .iter()
+                .map(|value| value.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>();
+            Ok::<_, Infallible>(Response::new(values))
+        });
+        let make = |request: &Request<()>| {
+            let token = request.uri().path().trim_start_matches('/').to_owned();
+            async move {
+                match token.as_str() {
+                    "" => Ok(None),
+                    "error" => Err("token provider is unavailable"),
+                    token => Ok(Some(
+                        HeaderValue::try_from(format!("Bearer {token}")).unwrap(),
+                    )),
+                }
+            }
+        };
+        let request = |path: &str| {
+            Request::builder()
+                .uri(path)
+                .header(AUTHORIZATION, "Basic old")
+                .body(())
+                .unwrap()
+        };
+
+        let service = AsyncSetRequestHeader::overriding(inner, AUTHORIZATION, make);
+        let response = service.clone().oneshot(request("/token")).await.unwrap();
+        assert_eq!(response.body(), &["Bearer token"]);
+        // No value leaves the request untouched.
+        let response = service.clone().oneshot(request("/")).await.unwrap();
+        assert_eq!(response.body(), &["Basic old"]);
+        // The maker error is returned as the service error.
+        let error = service.oneshot(request("/error")).await.unwrap_err();
+        assert_eq!(error.to_string(), "token provider is unavailable");
+
+        let service = AsyncSetRequestHeader::appending(inner, AUTHORIZATION, make);
+        let response = service.oneshot(request("/token")).await.unwrap();
+        assert_eq!(response.body(), &["Basic old", "Bearer token"]);
+
+        let service = AsyncSetRequestHeader::if_not_present(inner, AUTHORIZATION, make);
+        let response = service.clone().oneshot(request("/token")).await.unwrap();
+        assert_eq!(response.body(), &["Basic old"]);
+        let response = service
+            .oneshot(Request::builder().uri("/token").body(()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.body(), &["Bearer token"]);
+    }
+
+    #[tokio::test]
+    async fn test_set_request_header_from_extension() {
+        #[derive(Clone)]
+        struct CorrelationId(&'static str);
+
+        let inner = service_fn(|request: Request<()>| async move {
+            let values = request
+                .headers()
+                .get_all("x-correlation-id")
+                .iter()
+                .map(|value| value.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>();
+            Ok::<_, Infallible>(Response::new(values))
+        });
+        let make = |id: &CorrelationId| HeaderValue::try_from(id.0).ok();
+        let request = |id: Option<&'static str>| {
+            let mut request = Request::builder()
+                .header("x-correlation-id", "old")
+                .body(())
+                .unwrap();
+            if let Some(id) = id {
+                request.extensions_mut().insert(CorrelationId(id));
+            }
+            request
+        };
+        let name = http::HeaderName::from_static("x-correlation-id");
+
+        let service = SetRequestHeaderFromExtension::overriding(inner, name.clone(), make);
+        let response = service.clone().oneshot(request(Some("42"))).await.unwrap();
+        assert_eq!(response.body(), &["42"]);
+        // The missing extension leaves the header untouched.
+        let response = service.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(response.body(), &["old"]);
+        // So does the value which cannot be converted.
+        let response = service.oneshot(request(Some("bad\n"))).await.unwrap();
+        assert_eq!(response.body(), &["old"]);
+
+        let service = SetRequestHeaderFromExtension::appending(inner, name.clone(), make);
+        let response = service.oneshot(request(Some("42"))).await.unwrap();
+        assert_eq!(response.body(), &["old", "42"]);
+
+        let service = SetRequestHeaderFromExtension::if_not_present(inner, name, make);
+        let response = service.oneshot(request(Some("42"))).await.unwrap();
+        assert_eq!(response.body(), &["old"]);
+    }
+}
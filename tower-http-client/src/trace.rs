@@ -0,0 +1,430 @@
+//! Middleware that instruments outgoing requests with [`tracing`] spans.
+//!
+//! Each request is wrapped into the `request` span with the following fields:
+//!
+//! - `method`, `host` and `path` of the request URI.
+//! - `request_id` with the value of the [`tower-http`] `RequestId` request extension, or of
+//!   the `x-request-id` header, if present. Both of them are set by the `SetRequestIdLayer`,
+//!   so the trace layer should be placed below it.
+//! - `headers` with the request headers, if [enabled](TraceLayer::record_headers). Values of
+//!   the sensitive headers are redacted.
+//! - `status` and `latency_ms` of the received response, or `error` of the failed request.
+//!
+//! The event is also emitted when the request is completed, so the outcome is visible even
+//! without the span exporter.
+//!
+//! The middleware operates on the [`http::Request`] level, so it can be placed above the
+//! client adapter.
+//!
+//! # Example
+//!
+//! ```
+//! use tower::ServiceBuilder;
+//! use tower_http_client::trace::TraceLayer;
+//! use tower_reqwest::HttpClientLayer;
+//!
+//! let client = ServiceBuilder::new()
+//!     .layer(
+//!         TraceLayer::new()
+//!             .level(tracing::Level::INFO)
+//!             .record_headers(true),
+//!     )
+//!     .layer(HttpClientLayer)
+//!     .service(reqwest::Client::new());
+//! ```
+//!
+//! [`tracing`]: https://docs.rs/tracing
+//! [`tower-http`]: https://docs.rs/tower-http/latest/tower_http/request_id/index.html
+
+use std::{
+    fmt::Display,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use http::{
+    header::{AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION},
+    HeaderMap, HeaderName, Request, Response,
+};
+use pin_project::pin_project;
+use tower_http::request_id::RequestId;
+use tower_layer::Layer;
+use tower_service::Service;
+use tracing::{field, Level, Span};
+
+/// The default name of the header carrying the request ID.
+const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+// Creates a span with the level known only at runtime.
+macro_rules! dynamic_span {
+    ($level:expr, $($fields:tt)*) => {
+        match $level {
+            Level::ERROR => tracing::error_span!($($fields)*),
+            Level::WARN => tracing::warn_span!($($fields)*),
+            Level::INFO => tracing::info_span!($($fields)*),
+            Level::DEBUG => tracing::debug_span!($($fields)*),
+            Level::TRACE => tracing::trace_span!($($fields)*),
+        }
+    };
+}
+
+// Emits an event with the level known only at runtime.
+macro_rules! dynamic_event {
+    ($level:expr, $($fields:tt)*) => {
+        match $level {
+            Level::ERROR => tracing::error!($($fields)*),
+            Level::WARN => tracing::warn!($($fields)*),
+            Level::INFO => tracing::info!($($fields)*),
+            Level::DEBUG => tracing::debug!($($fields)*),
+            Level::TRACE => tracing::trace!($($fields)*),
+        }
+    };
+}
+
+/// Layer that applies the [`Trace`] middleware.
+#[derive(Debug, Clone)]
+pub struct TraceLayer {
+    level: Level,
+    record_headers: bool,
+    sensitive_headers: Arc<[HeaderName]>,
+}
+
+impl Default for TraceLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TraceLayer {
+    /// Creates a new layer with the `DEBUG` level, which doesn't record headers.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            level: Level::DEBUG,
+            record_headers: false,
+            sensitive_headers: Arc::new([AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE]),
+        }
+    }
+
+    /// Sets the level of the request spans and events.
+    #[must_use]
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets whether the request headers should be recorded.
+    #[must_use]
+    pub fn record_headers(mut self, record: bool) -> Self {
+        self.record_headers = record;
+        self
+    }
+
+    /// Sets the headers, whose values should be redacted.
+    ///
+    /// By default the `Authorization`, `Proxy-Authorization` and `Cookie` headers are
+    /// redacted. The header values [marked](http::HeaderValue::set_sensitive) as sensitive are
+    /// always redacted.
+    #[must_use]
+    pub fn sensitive_headers(mut self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+        self.sensitive_headers = headers.into_iter().collect();
+        self
+    }
+}
+
+impl<S> Layer<S> for TraceLayer {
+    type Service = Trace<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Trace {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Middleware that instruments outgoing requests with [`tracing`] spans.
+///
+/// See the [module docs](self) for more details.
+///
+/// [`tracing`]: https://docs.rs/tracing
+#[derive(Debug, Clone)]
+pub struct Trace<S> {
+    inner: S,
+    layer: TraceLayer,
+}
+
+impl<S> Trace<S> {
+    /// Creates a new middleware with the default settings.
+    pub fn new(inner: S) -> Self {
+        TraceLayer::new().layer(inner)
+    }
+}
+
+impl<S, ReqBody, RespBody> Service<Request<ReqBody>> for Trace<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<RespBody>>,
+    S::Error: Display,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let span = dynamic_span!(
+            self.layer.level,
+            "request",
+            method = %req.method(),
+            host = req.uri().host(),
+            path = req.uri().path(),
+            request_id = field::Empty,
+            headers = field::Empty,
+            status = field::Empty,
+            latency_ms = field::Empty,
+            error = field::Empty,
+        );
+        if let Some(request_id) = req
+            .extensions()
+            .get::<RequestId>()
+            .map(RequestId::header_value)
+            .or_else(|| req.headers().get(X_REQUEST_ID))
+            .and_then(|value| value.to_str().ok())
+        {
+            span.record("request_id", request_id);
+        }
+        if self.layer.record_headers {
+            let headers = recorded_headers(req.headers(), &self.layer.sensitive_headers);
+            span.record("headers", field::debug(headers));
+        }
+
+        let inner = span.in_scope(|| self.inner.call(req));
+        ResponseFuture {
+            inner,
+            span,
+            level: self.layer.level,
+            start: Instant::now(),
+        }
+    }
+}
+
+/// Response future for the [`Trace`] middleware.
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    span: Span,
+    level: Level,
+    start: Instant,
+}
+
+impl<F, RespBody, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<RespBody>, E>>,
+    E: Display,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _entered = this.span.enter();
+        let result = std::task::ready!(this.inner.poll(cx));
+
+        let latency_ms = this.start.elapsed().as_millis();
+        this.span.record("latency_ms", latency_ms);
+        match &result {
+            Ok(response) => {
+                this.span.record("status", response.status().as_u16());
+                dynamic_event!(
+                    *this.level,
+                    status = response.status().as_u16(),
+                    latency_ms,
+                    "response received"
+                );
+            }
+            Err(error) => {
+                this.span.record("error", field::display(error));
+                dynamic_event!(*this.level, %error, latency_ms, "request failed");
+            }
+        }
+        Poll::Ready(result)
+    }
+}
+
+// Collects the header names and values with the sensitive ones redacted.
+fn recorded_headers<'a>(
+    headers: &'a HeaderMap,
+    sensitive: &[HeaderName],
+) -> Vec<(&'a str, &'a str)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if value.is_sensitive() || sensitive.contains(name) {
+                "[redacted]"
+            } else {
+                value.to_str().unwrap_or("[non-ascii]")
+            };
+            (name.as_str(), value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        fmt,
+        sync::{Arc, Mutex},
+    };
+
+    use http::{
+        header::{ACCEPT, AUTHORIZATION, COOKIE},
+        HeaderMap, HeaderValue, Request, Response,
+    };
+    use tower::{service_fn, ServiceExt as _};
+    use tower_http::request_id::RequestId;
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    use super::{recorded_headers, Trace, TraceLayer};
+
+    type Fields = Arc<Mutex<BTreeMap<&'static str, String>>>;
+
+    // Subscriber which records the fields of all spans into a single map.
+    #[derive(Default)]
+    struct SpanRecorder {
+        fields: Fields,
+    }
+
+    struct FieldVisitor<'a>(&'a mut BTreeMap<&'static str, String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.insert(field.name(), format!("{value:?}"));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name(), value.to_owned());
+        }
+    }
+
+    impl Subscriber for SpanRecorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            span.record(&mut FieldVisitor(&mut self.fields.lock().unwrap()));
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &span::Id, values: &span::Record<'_>) {
+            values.record(&mut FieldVisitor(&mut self.fields.lock().unwrap()));
+        }
+
+        fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+        fn event(&self, _event: &Event<'_>) {}
+
+        fn enter(&self, _span: &span::Id) {}
+
+        fn exit(&self, _span: &span::Id) {}
+    }
+
+    // Sends the request through the trace layer and returns the recorded span fields.
+    async fn traced_fields(request: Request<()>) -> BTreeMap<&'static str, String> {
+        let recorder = SpanRecorder::default();
+        let fields = recorder.fields.clone();
+        let _guard = tracing::subscriber::set_default(recorder);
+
+        let service = Trace::new(service_fn(|request: Request<()>| async move {
+            if request.uri().path() == "/error" {
+                Err("connection refused")
+            } else {
+                Ok(Response::new(()))
+            }
+        }));
+        let _ = service.oneshot(request).await;
+
+        let fields = fields.lock().unwrap().clone();
+        fields
+    }
+
+    #[test]
+    fn test_trace_recorded_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer token"));
+        let mut cookie = HeaderValue::from_static("session=secret");
+        cookie.set_sensitive(true);
+        headers.insert(COOKIE, cookie);
+
+        let layer = TraceLayer::new().sensitive_headers([AUTHORIZATION]);
+        assert_eq!(
+            recorded_headers(&headers, &layer.sensitive_headers),
+            [
+                ("accept", "*/*"),
+                ("authorization", "[redacted]"),
+                ("cookie", "[redacted]"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_trace_passes_through() {
+        let service = Trace::new(service_fn(|request: Request<()>| async move {
+            if request.uri().path() == "/error" {
+                Err("connection refused")
+            } else {
+                Ok(Response::new(request.uri().path().to_owned()))
+            }
+        }));
+
+        let request = Request::builder().uri("/hello").body(()).unwrap();
+        let response = service.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.body(), "/hello");
+
+        let request = Request::builder().uri("/error").body(()).unwrap();
+        let error = service.oneshot(request).await.unwrap_err();
+        assert_eq!(error, "connection refused");
+    }
+
+    #[tokio::test]
+    async fn test_trace_span_fields() {
+        let request = Request::post("http://example.com/hello?name=world")
+            .header("x-request-id", "from-header")
+            .body(())
+            .unwrap();
+        let fields = traced_fields(request).await;
+        assert_eq!(fields["method"], "POST");
+        assert_eq!(fields["host"], "example.com");
+        assert_eq!(fields["path"], "/hello");
+        assert_eq!(fields["request_id"], "from-header");
+        assert_eq!(fields["status"], "200");
+        assert!(fields.contains_key("latency_ms"));
+        assert!(!fields.contains_key("error"));
+
+        // The request extension takes precedence over the header.
+        let mut request = Request::get("http://example.com/error")
+            .header("x-request-id", "from-header")
+            .body(())
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(RequestId::new(HeaderValue::from_static("from-extension")));
+        let fields = traced_fields(request).await;
+        assert_eq!(fields["request_id"], "from-extension");
+        assert_eq!(fields["error"], "connection refused");
+        assert!(fields.contains_key("latency_ms"));
+        assert!(!fields.contains_key("status"));
+    }
+}
@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use futures_util::{Stream, TryStreamExt as _};
+use tower::{
+    limit::{ConcurrencyLimitLayer, RateLimitLayer},
+    ServiceBuilder, ServiceExt as _,
+};
+use tower_service::Service;
+
+use crate::BoxError;
+
+/// Helper that sends a stream of requests concurrently with the limited rate.
+///
+/// It wraps the client into the [`ConcurrencyLimit`] and [`RateLimit`] middlewares and
+/// drives the requests via the [`CallAll`] stream, which waits for the client readiness
+/// before sending each request. So there is no need to buffer the client or poll its
+/// readiness manually.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use futures_util::{stream, StreamExt as _};
+/// use tower_http_client::{util::ConcurrentExecutor, ServiceExt as _};
+/// use tower_reqwest::HttpClientService;
+///
+/// #[tokio::main]
+/// async fn main() -> anyhow::Result<()> {
+///     let mut client = HttpClientService::new(reqwest::Client::new());
+///     let requests = (0..10)
+///         .map(|i| client.get(format!("http://example.com/items/{i}")).build())
+///         .collect::<Result<Vec<_>, _>>()?;
+///
+///     let mut responses = ConcurrentExecutor::new(4)
+///         .rate_limit(2, Duration::from_secs(1))
+///         .execute(client, stream::iter(requests));
+///     while let Some(response) = responses.next().await {
+///         println!("{}", response.map_err(anyhow::Error::msg)?.status());
+///     }
+///     Ok(())
+/// }
+/// ```
+///
+/// [`ConcurrencyLimit`]: tower::limit::ConcurrencyLimit
+/// [`RateLimit`]: tower::limit::RateLimit
+/// [`CallAll`]: tower::util::CallAll
+#[derive(Debug, Clone, Copy)]
+pub struct ConcurrentExecutor {
+    concurrency: usize,
+    rate: Option<(u64, Duration)>,
+}
+
+impl ConcurrentExecutor {
+    /// Creates a new executor, which sends at most `concurrency` requests at once.
+    ///
+    /// By default the rate is not limited.
+    #[must_use]
+    pub const fn new(concurrency: usize) -> Self {
+        Self {
+            concurrency,
+            rate: None,
+        }
+    }
+
+    /// Limits the rate of requests to `num` requests per the `per` duration.
+    #[must_use]
+    pub const fn rate_limit(mut self, num: u64, per: Duration) -> Self {
+        self.rate = Some((num, per));
+        self
+    }
+
+    /// Sends the given requests using the client and returns a stream of results.
+    ///
+    /// The results are yielded in the order of the requests. If the client fails
+    /// to become ready, the error is yielded and the stream ends.
+    pub fn execute<S, R, St>(
+        &self,
+        client: S,
+        requests: St,
+    ) -> impl Stream<Item = Result<S::Response, BoxError>>
+    where
+        S: Service<R>,
+        S::Error: Into<BoxError>,
+        St: Stream<Item = R>,
+    {
+        let client = ServiceBuilder::new()
+            .option_layer(self.rate.map(|(num, per)| RateLimitLayer::new(num, per)))
+            .layer(ConcurrencyLimitLayer::new(self.concurrency))
+            .service(client);
+        client.call_all(requests).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use futures_util::{stream, StreamExt as _};
+    use tower::service_fn;
+
+    use super::ConcurrentExecutor;
+
+    #[tokio::test]
+    async fn test_concurrent_executor() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let client = service_fn({
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            move |request: usize| {
+                let in_flight = in_flight.clone();
+                let max_in_flight = max_in_flight.clone();
+                async move {
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(current, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok::<_, Infallible>(request * 2)
+                }
+            }
+        });
+
+        let results = ConcurrentExecutor::new(3)
+            .rate_limit(100, Duration::from_secs(1))
+            .execute(client, stream::iter(0..10))
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(results, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+        assert!(max_in_flight.load(Ordering::SeqCst) <= 3);
+    }
+}
@@ -1,5 +1,9 @@
 //! Various extra utility types and functions.
 
 pub use self::boxed_clone_sync::BoxCloneSyncService;
+#[cfg(feature = "executor")]
+pub use self::concurrent_executor::ConcurrentExecutor;
 
 mod boxed_clone_sync;
+#[cfg(feature = "executor")]
+mod concurrent_executor;
@@ -0,0 +1,124 @@
+//! Compile-time checks that the common client stacks satisfy the bounds required
+//! by the public API.
+
+use bytes::Bytes;
+use http::{
+    header::{ACCEPT, ACCEPT_LANGUAGE, USER_AGENT},
+    HeaderValue,
+};
+use http_body_util::Full;
+use reqwest::Client;
+use tower::ServiceBuilder;
+use tower_http::ServiceBuilderExt as _;
+use tower_http_client::{util::BoxCloneSyncService, ServiceExt as _};
+use tower_reqwest::{into_reqwest_body, HttpClientLayer};
+use tower_service::Service;
+
+// Checks that the given service can be used via the `ServiceExt` trait and shared between tasks.
+fn assert_client<S, ReqBody, RespBody, Err>(service: &S)
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<RespBody>, Error = Err>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    S::Future: Send + 'static,
+    Err: 'static,
+{
+    let _ = service;
+}
+
+fn assert_send<T: Send>(value: T) -> T {
+    value
+}
+
+// Checks that the bare adapter satisfies the required bounds.
+#[test]
+fn test_bounds_reqwest_adapter() -> anyhow::Result<()> {
+    let mut client = ServiceBuilder::new()
+        .layer(HttpClientLayer)
+        .service(Client::new());
+    assert_client(&client);
+
+    let _future = assert_send(client.get("http://localhost").send()?);
+    Ok(())
+}
+
+// Checks that the adapter can be used with the `Full<Bytes>` request body.
+#[test]
+fn test_bounds_reqwest_adapter_full_body() -> anyhow::Result<()> {
+    let mut client = ServiceBuilder::new()
+        .map_request_body(|body: Full<Bytes>| into_reqwest_body(body))
+        .layer(HttpClientLayer)
+        .service(Client::new());
+    assert_client(&client);
+
+    let _future = assert_send(
+        client
+            .post("http://localhost")
+            .body::<Full<Bytes>>(Bytes::from_static(b"Hello"))
+            .send()?,
+    );
+    Ok(())
+}
+
+// Checks that the boxed client satisfies the required bounds.
+#[test]
+fn test_bounds_boxed_client() -> anyhow::Result<()> {
+    type HttpClient = BoxCloneSyncService<
+        http::Request<reqwest::Body>,
+        http::Response<reqwest::Body>,
+        anyhow::Error,
+    >;
+
+    let mut client: HttpClient = ServiceBuilder::new()
+        .layer(BoxCloneSyncService::layer())
+        .map_err(anyhow::Error::from)
+        .layer(HttpClientLayer)
+        .service(Client::new());
+    assert_client(&client);
+
+    let _future = assert_send(client.get("http://localhost").send()?);
+    Ok(())
+}
+
+// Checks that the client with the common `tower-http` layers satisfies the required bounds.
+//
+// These layers are generic over the request body, so they can be placed above the adapter.
+#[test]
+fn test_bounds_tower_http_layers() -> anyhow::Result<()> {
+    let mut client = ServiceBuilder::new()
+        .override_request_header(USER_AGENT, HeaderValue::from_static("tower-http-client"))
+        .append_request_header(ACCEPT, HeaderValue::from_static("application/json"))
+        .insert_request_header_if_not_present(ACCEPT_LANGUAGE, HeaderValue::from_static("en"))
+        .layer(tower_http::auth::AddAuthorizationLayer::bearer("token"))
+        .layer(HttpClientLayer)
+        .service(Client::new());
+    assert_client(&client);
+
+    let _future = assert_send(client.get("http://localhost").send()?);
+    Ok(())
+}
+
+// Checks that the boxed client with the `tower-http` authorization layer satisfies the required bounds.
+#[test]
+fn test_bounds_boxed_client_add_authorization() -> anyhow::Result<()> {
+    type HttpClient = BoxCloneSyncService<
+        http::Request<reqwest::Body>,
+        http::Response<reqwest::Body>,
+        anyhow::Error,
+    >;
+
+    let mut client: HttpClient = ServiceBuilder::new()
+        .layer(BoxCloneSyncService::layer())
+        .layer(tower_http::auth::AddAuthorizationLayer::basic(
+            "user", "password",
+        ))
+        .map_err(anyhow::Error::from)
+        .layer(HttpClientLayer)
+        .service(Client::new());
+    assert_client(&client);
+
+    let _future = assert_send(client.get("http://localhost").send()?);
+    Ok(())
+}
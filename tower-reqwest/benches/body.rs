@@ -0,0 +1,61 @@
+//! Benchmarks of sending the in-memory request body through the streaming and sized
+//! conversions.
+
+use std::net::SocketAddr;
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use http_body_util::{BodyExt as _, Empty, Full};
+use hyper::{server::conn::http1, service::service_fn};
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpListener;
+use tower_reqwest::{into_reqwest_body, into_reqwest_body_sized};
+
+const BODY_SIZE: usize = 1024 * 1024;
+
+// Starts the server, which reads the whole request body and responds with no content.
+async fn start_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let service = service_fn(|request: http::Request<hyper::body::Incoming>| async move {
+                request.into_body().collect().await?;
+                Ok::<_, hyper::Error>(http::Response::new(Empty::<Bytes>::new()))
+            });
+            tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+        }
+    });
+    addr
+}
+
+fn bench_body(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let uri = format!("http://{}", runtime.block_on(start_server()));
+    let client = reqwest::Client::new();
+    let body = Bytes::from(vec![b'x'; BODY_SIZE]);
+
+    let mut group = c.benchmark_group("full_1mib");
+    group.throughput(Throughput::Bytes(BODY_SIZE as u64));
+    group.bench_function("into_reqwest_body", |b| {
+        b.to_async(&runtime).iter(|| {
+            let request = client
+                .post(&uri)
+                .body(into_reqwest_body(Full::new(body.clone())));
+            async move { request.send().await.unwrap() }
+        });
+    });
+    group.bench_function("into_reqwest_body_sized", |b| {
+        b.to_async(&runtime).iter(|| {
+            let request = client
+                .post(&uri)
+                .body(into_reqwest_body_sized(Full::new(body.clone())));
+            async move { request.send().await.unwrap() }
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_body);
+criterion_main!(benches);
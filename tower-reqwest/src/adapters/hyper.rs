@@ -0,0 +1,158 @@
+//! Adapter for [`hyper`] client.
+//!
+//! The [`hyper_util`] client is wrapped into the [`HyperClient`] type, since it can also
+//! accept the `reqwest::Body` requests, which would conflict with the [`reqwest`] adapter.
+//!
+//! [`hyper`]: https://crates.io/crates/hyper
+//! [`hyper_util`]: https://crates.io/crates/hyper-util
+//! [`reqwest`]: https://crates.io/crates/reqwest
+
+use std::{future::Future, task::Poll};
+
+use http_body::Body;
+use hyper::body::Incoming;
+use hyper_util::client::legacy::{connect::Connect, Client, ResponseFuture};
+use pin_project::pin_project;
+use tower_service::Service;
+
+use super::insert_remote_addr;
+use crate::{error::BoxError, HttpClientService};
+
+/// A [`hyper_util`] legacy client, which can be wrapped into the [`HttpClientService`].
+///
+/// # Example
+///
+/// ```
+/// use http_body_util::Empty;
+/// use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+/// use tower::ServiceBuilder;
+/// use tower_reqwest::{HttpClientLayer, HyperClient};
+///
+/// let client = Client::builder(TokioExecutor::new()).build_http::<Empty<bytes::Bytes>>();
+/// let service = ServiceBuilder::new()
+///     .layer(HttpClientLayer)
+///     .service(HyperClient::new(client));
+/// ```
+///
+/// [`hyper_util`]: https://crates.io/crates/hyper-util
+#[derive(Debug, Clone)]
+pub struct HyperClient<C, B>(Client<C, B>);
+
+impl<C, B> HyperClient<C, B> {
+    /// Creates a new wrapper of the given client.
+    pub const fn new(client: Client<C, B>) -> Self {
+        Self(client)
+    }
+
+    /// Returns the wrapped client.
+    pub fn into_inner(self) -> Client<C, B> {
+        self.0
+    }
+}
+
+impl<C, B> From<Client<C, B>> for HyperClient<C, B> {
+    fn from(client: Client<C, B>) -> Self {
+        Self(client)
+    }
+}
+
+impl<C, B> Service<http::Request<B>> for HttpClientService<HyperClient<C, B>>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+    B: Body + Send + Unpin + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Response = http::Response<Incoming>;
+    type Error = crate::Error;
+    type Future = ExecuteRequestFuture;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.0 .0.poll_ready(cx).map_err(crate::Error::from)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        ExecuteRequestFuture {
+            inner: self.0 .0.call(req),
+        }
+    }
+}
+
+#[pin_project]
+/// Future that resolves to the response or failure to connect.
+#[derive(Debug)]
+pub struct ExecuteRequestFuture {
+    #[pin]
+    inner: ResponseFuture,
+}
+
+impl Future for ExecuteRequestFuture {
+    type Output = crate::Result<http::Response<Incoming>>;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        match self.project().inner.poll(cx) {
+            Poll::Ready(result) => {
+                Poll::Ready(result.map(insert_remote_addr).map_err(crate::Error::from))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header::USER_AGENT, HeaderValue};
+    use http_body_util::{BodyExt, Empty};
+    use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+    use pretty_assertions::assert_eq;
+    use tower::{Service, ServiceBuilder};
+    use tower_http::ServiceBuilderExt;
+    use wiremock::{
+        matchers::{header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::HyperClient;
+    use crate::{HttpClientLayer, RemoteAddr};
+
+    #[tokio::test]
+    async fn test_hyper_client_layer() -> anyhow::Result<()> {
+        let mock_server = MockServer::start().await;
+        let mock_uri = mock_server.uri();
+
+        Mock::given(method("GET"))
+            .and(path("/hello"))
+            .and(header(USER_AGENT, "tower-reqwest"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("Hello world"))
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::builder(TokioExecutor::new()).build_http::<Empty<bytes::Bytes>>();
+        let mut service = ServiceBuilder::new()
+            .override_request_header(USER_AGENT, HeaderValue::from_static("tower-reqwest"))
+            .layer(HttpClientLayer)
+            .service(HyperClient::new(client));
+
+        let request = http::request::Builder::new()
+            .method(http::Method::GET)
+            .uri(format!("{mock_uri}/hello"))
+            .body(Empty::new())?;
+        let response = service.call(request).await?;
+        assert!(response.status().is_success());
+        assert_eq!(
+            response.extensions().get::<RemoteAddr>(),
+            Some(&RemoteAddr(*mock_server.address()))
+        );
+
+        let bytes = response.into_body().collect().await?.to_bytes();
+        assert_eq!(bytes, "Hello world");
+
+        Ok(())
+    }
+}
@@ -1,3 +1,23 @@
 //! Adapters for various clients
 
+#[cfg(feature = "hyper")]
+pub mod hyper;
+#[cfg(feature = "reqwest")]
 pub mod reqwest;
+
+/// Copies the remote address reported by the connector into the [`RemoteAddr`] extension.
+///
+/// [`RemoteAddr`]: crate::RemoteAddr
+#[cfg(any(feature = "hyper", feature = "reqwest"))]
+fn insert_remote_addr<B>(mut response: http::Response<B>) -> http::Response<B> {
+    let remote_addr = response
+        .extensions()
+        .get::<hyper_util::client::legacy::connect::HttpInfo>()
+        .map(hyper_util::client::legacy::connect::HttpInfo::remote_addr);
+    if let Some(remote_addr) = remote_addr {
+        response
+            .extensions_mut()
+            .insert(crate::RemoteAddr(remote_addr));
+    }
+    response
+}
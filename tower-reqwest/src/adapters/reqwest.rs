@@ -2,12 +2,13 @@
 //!
 //! [`reqwest`]: https://crates.io/crates/reqwest
 
-use std::{future::Future, task::Poll};
+use std::{future::Future, task::Poll, time::Duration};
 
 use pin_project::pin_project;
 use tower_service::Service;
 
-use crate::HttpClientService;
+use super::insert_remote_addr;
+use crate::{error::ClientError, HttpClientService, RequestTimeout};
 
 impl<S> Service<http::Request<reqwest::Body>> for HttpClientService<S>
 where
@@ -23,14 +24,19 @@ where
 
     fn poll_ready(
         &mut self,
-        _cx: &mut std::task::Context<'_>,
+        cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Result<(), Self::Error>> {
-        Poll::Ready(Ok(()))
+        self.0.poll_ready(cx).map_err(crate::Error::from)
     }
 
     fn call(&mut self, req: http::Request<reqwest::Body>) -> Self::Future {
+        // The extensions are lost during the conversion, so read the timeout beforehand.
+        let timeout = req
+            .extensions()
+            .get::<RequestTimeout>()
+            .map(|timeout| timeout.0);
         let future = reqwest::Request::try_from(req).map(|reqw| self.0.call(reqw));
-        ExecuteRequestFuture::new(future)
+        ExecuteRequestFuture::new(future, timeout)
     }
 }
 
@@ -52,6 +58,13 @@ enum Inner<F> {
         #[pin]
         fut: F,
     },
+    Timed {
+        #[pin]
+        fut: F,
+        #[pin]
+        sleep: tokio::time::Sleep,
+        timeout: Duration,
+    },
     Error {
         error: Option<crate::Error>,
     },
@@ -61,10 +74,15 @@ impl<S> ExecuteRequestFuture<S>
 where
     S: Service<reqwest::Request>,
 {
-    fn new(future: Result<S::Future, reqwest::Error>) -> Self {
-        let inner = match future {
-            Ok(fut) => Inner::Future { fut },
-            Err(error) => Inner::Error {
+    fn new(future: Result<S::Future, reqwest::Error>, timeout: Option<Duration>) -> Self {
+        let inner = match (future, timeout) {
+            (Ok(fut), None) => Inner::Future { fut },
+            (Ok(fut), Some(timeout)) => Inner::Timed {
+                fut,
+                sleep: tokio::time::sleep(timeout),
+                timeout,
+            },
+            (Err(error), _) => Inner::Error {
                 error: Some(error.into()),
             },
         };
@@ -86,8 +104,25 @@ where
     ) -> std::task::Poll<Self::Output> {
         let this = self.project();
         match this.inner.project() {
-            InnerProj::Future { fut } => {
-                fut.poll(cx).map_ok(From::from).map_err(crate::Error::from)
+            InnerProj::Future { fut } => fut
+                .poll(cx)
+                .map_ok(|response| insert_remote_addr(response.into()))
+                .map_err(crate::Error::from),
+            InnerProj::Timed {
+                fut,
+                sleep,
+                timeout,
+            } => {
+                if let Poll::Ready(result) = fut.poll(cx) {
+                    return Poll::Ready(
+                        result
+                            .map(|response| insert_remote_addr(response.into()))
+                            .map_err(crate::Error::from),
+                    );
+                }
+                sleep
+                    .poll(cx)
+                    .map(|()| Err(ClientError::timeout(*timeout).into()))
             }
             InnerProj::Error { error } => {
                 let error = error.take().expect("Polled after ready");
@@ -99,6 +134,8 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use http::{header::USER_AGENT, HeaderName, HeaderValue};
     use http_body_util::BodyExt;
     use pretty_assertions::assert_eq;
@@ -111,7 +148,7 @@ mod tests {
         Mock, MockServer, ResponseTemplate,
     };
 
-    use crate::HttpClientLayer;
+    use crate::{error::ClientError, HttpClientLayer, RemoteAddr, RequestTimeout};
 
     #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
     struct Info {
@@ -170,6 +207,10 @@ mod tests {
             .call(request)
             .await?;
         assert!(response.status().is_success());
+        assert_eq!(
+            response.extensions().get::<RemoteAddr>(),
+            Some(&RemoteAddr(*mock_server.address()))
+        );
         // Try to read body
         let info = Info::from_body(response.into_body()).await?;
         assert!(info.request_id.is_none());
@@ -203,4 +244,134 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_http_client_poll_ready() -> anyhow::Result<()> {
+        let mock_server = MockServer::start().await;
+        let mock_uri = mock_server.uri();
+        Mock::given(method("GET"))
+            .and(path("/hello"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        // The limit is placed below the adapter, so its readiness must be propagated.
+        let mut client = ServiceBuilder::new()
+            .layer(HttpClientLayer)
+            .concurrency_limit(1)
+            .service(Client::new());
+        let request =
+            || http::Request::get(format!("{mock_uri}/hello")).body(reqwest::Body::default());
+
+        let response = client.ready().await?.call(request()?);
+        let mut cx = std::task::Context::from_waker(futures_util::task::noop_waker_ref());
+        assert!(client.poll_ready(&mut cx).is_pending());
+
+        assert_eq!(response.await?.status(), 200);
+        let response = client.ready().await?.call(request()?).await?;
+        assert_eq!(response.status(), 200);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_client_request_timeout() -> anyhow::Result<()> {
+        let mock_server = MockServer::start().await;
+        let mock_uri = mock_server.uri();
+
+        Mock::given(method("GET"))
+            .and(path("/slow"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&mock_server)
+            .await;
+
+        let mut service = ServiceBuilder::new()
+            .layer(HttpClientLayer)
+            .service(Client::new());
+
+        let request = http::request::Builder::new()
+            .uri(format!("{mock_uri}/slow"))
+            .extension(RequestTimeout(Duration::from_millis(50)))
+            .body(reqwest::Body::default())?;
+        let error = service.call(request).await.unwrap_err();
+
+        let crate::Error::Client(error) = error else {
+            panic!("unexpected error: {error}");
+        };
+        assert!(error.is_timeout());
+        assert!(!error.is_connection());
+        let source = std::error::Error::source(&error).unwrap();
+        assert!(source.is::<crate::error::TimeoutError>());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_client_error_kinds() -> anyhow::Result<()> {
+        let mut service = ServiceBuilder::new()
+            .layer(HttpClientLayer)
+            .service(Client::new());
+
+        // The relative URI cannot be converted into the `reqwest::Request`.
+        let request = http::request::Builder::new()
+            .uri("/hello")
+            .body(reqwest::Body::default())?;
+        let error = service.call(request).await.unwrap_err();
+        assert!(error.is_request());
+        assert!(!error.is_connect() && !error.is_timeout());
+
+        // Nothing listens on the discard port.
+        let request = http::request::Builder::new()
+            .uri("http://127.0.0.1:9")
+            .body(reqwest::Body::default())?;
+        let error = service.call(request).await.unwrap_err();
+        assert!(error.is_connect());
+        assert!(!error.is_request() && !error.is_timeout());
+        assert_eq!(error.status(), None);
+
+        // The client errors wrapped by middlewares are inspected too.
+        let error = crate::Error::Middleware(Box::new(ClientError::timeout(Duration::ZERO)));
+        assert!(error.is_timeout());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_into_reqwest_body_sized() -> anyhow::Result<()> {
+        use bytes::Bytes;
+        use http_body::Frame;
+        use http_body_util::{Full, StreamBody};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+        let mut client = ServiceBuilder::new()
+            .layer(HttpClientLayer)
+            .service(Client::new());
+
+        // The in-memory body is sent with the `Content-Length` header.
+        let body = crate::into_reqwest_body_sized(Full::new(Bytes::from_static(b"hello")));
+        assert_eq!(body.as_bytes(), Some(&b"hello"[..]));
+        let request = http::Request::post(mock_server.uri()).body(body)?;
+        client.ready().await?.call(request).await?;
+
+        // The streaming body is still streamed.
+        let frames = [Ok::<_, std::convert::Infallible>(Frame::data(
+            Bytes::from_static(b"hello"),
+        ))];
+        let body =
+            crate::into_reqwest_body_sized(StreamBody::new(futures_util::stream::iter(frames)));
+        assert_eq!(body.as_bytes(), None);
+        let request = http::Request::post(mock_server.uri()).body(body)?;
+        client.ready().await?.call(request).await?;
+
+        let requests = mock_server.received_requests().await.unwrap();
+        assert_eq!(requests[0].headers["content-length"], "5");
+        assert_eq!(requests[1].headers["transfer-encoding"], "chunked");
+        assert_eq!(requests[1].body, b"hello");
+
+        Ok(())
+    }
 }
@@ -14,6 +14,40 @@ pub enum Error {
     Middleware(BoxError),
 }
 
+impl Error {
+    /// Returns true if the error was caused by a timeout.
+    #[must_use]
+    pub fn is_timeout(&self) -> bool {
+        self.client_error().is_some_and(ClientError::is_timeout)
+    }
+
+    /// Returns true if the error is related to connect.
+    #[must_use]
+    pub fn is_connect(&self) -> bool {
+        self.client_error().is_some_and(ClientError::is_connection)
+    }
+
+    /// Returns true if the request couldn't be constructed from the given one.
+    #[must_use]
+    pub fn is_request(&self) -> bool {
+        self.client_error().is_some_and(ClientError::is_request)
+    }
+
+    /// Returns the response status, if the error was caused by it.
+    #[must_use]
+    pub fn status(&self) -> Option<http::StatusCode> {
+        self.client_error().and_then(ClientError::status)
+    }
+
+    /// Returns the client error, including the one wrapped by a middleware.
+    fn client_error(&self) -> Option<&ClientError> {
+        match self {
+            Self::Client(err) => Some(err),
+            Self::Middleware(err) => err.downcast_ref(),
+        }
+    }
+}
+
 /// An error that can occur while handling HTTP requests.
 #[derive(Debug, thiserror::Error)]
 #[error("{inner}")]
@@ -21,6 +55,7 @@ pub struct ClientError {
     #[source]
     inner: BoxError,
     kind: ClientErrorKind,
+    status: Option<http::StatusCode>,
 }
 
 impl ClientError {
@@ -33,7 +68,22 @@ impl ClientError {
     /// Returns true if the error is related to connect
     #[must_use]
     pub fn is_connection(&self) -> bool {
-        matches!(self.kind, ClientErrorKind::Timeout)
+        matches!(self.kind, ClientErrorKind::Connection)
+    }
+
+    /// Returns true if the request couldn't be constructed from the given one.
+    ///
+    /// For example, the [`http::Request`] with a relative URI cannot be converted into
+    /// the `reqwest::Request`.
+    #[must_use]
+    pub fn is_request(&self) -> bool {
+        matches!(self.kind, ClientErrorKind::Request)
+    }
+
+    /// Returns the response status, if the error was caused by it.
+    #[must_use]
+    pub const fn status(&self) -> Option<http::StatusCode> {
+        self.status
     }
 
     /// Returns true if the error is related to the request or response body.
@@ -43,20 +93,52 @@ impl ClientError {
     }
 }
 
+#[cfg(feature = "reqwest")]
+impl ClientError {
+    /// Creates an error for the request which exceeded the given [`RequestTimeout`].
+    ///
+    /// [`RequestTimeout`]: crate::RequestTimeout
+    pub(crate) fn timeout(timeout: std::time::Duration) -> Self {
+        Self {
+            inner: Box::new(TimeoutError { timeout }),
+            kind: ClientErrorKind::Timeout,
+            status: None,
+        }
+    }
+}
+
+/// The request didn't complete within the [`RequestTimeout`].
+///
+/// This error is the [source](std::error::Error::source) of the [`ClientError`] returned
+/// by the adapter, so it can be distinguished from the timeouts of the underlying client.
+///
+/// [`RequestTimeout`]: crate::RequestTimeout
+#[derive(Debug, thiserror::Error)]
+#[error("request timed out after {timeout:?}")]
+pub struct TimeoutError {
+    /// The elapsed timeout.
+    pub timeout: std::time::Duration,
+}
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(any(feature = "hyper", feature = "reqwest")), allow(dead_code))]
 enum ClientErrorKind {
     Timeout,
     Connection,
+    Request,
     Body,
     Other,
 }
 
+#[cfg(feature = "reqwest")]
 impl From<reqwest::Error> for ClientError {
     fn from(value: reqwest::Error) -> Self {
         let kind = if value.is_timeout() {
             ClientErrorKind::Timeout
         } else if value.is_connect() {
             ClientErrorKind::Connection
+        } else if value.is_builder() {
+            ClientErrorKind::Request
         } else if value.is_body() {
             ClientErrorKind::Body
         } else {
@@ -64,14 +146,51 @@ impl From<reqwest::Error> for ClientError {
         };
 
         Self {
+            status: value.status(),
             inner: Box::new(value),
             kind,
         }
     }
 }
 
+#[cfg(feature = "reqwest")]
 impl From<reqwest::Error> for Error {
     fn from(value: reqwest::Error) -> Self {
         Self::Client(value.into())
     }
 }
+
+#[cfg(feature = "hyper")]
+impl From<hyper_util::client::legacy::Error> for ClientError {
+    fn from(value: hyper_util::client::legacy::Error) -> Self {
+        let hyper_error = std::error::Error::source(&value)
+            .and_then(|source| source.downcast_ref::<hyper::Error>());
+
+        let kind = if value.is_connect() {
+            ClientErrorKind::Connection
+        } else if hyper_error.is_some_and(hyper::Error::is_timeout) {
+            ClientErrorKind::Timeout
+        } else if hyper_error
+            .is_some_and(|err| err.is_body_write_aborted() || err.is_incomplete_message())
+        {
+            ClientErrorKind::Body
+        } else if hyper_error.is_some_and(hyper::Error::is_user) {
+            ClientErrorKind::Request
+        } else {
+            ClientErrorKind::Other
+        };
+
+        Self {
+            inner: Box::new(value),
+            kind,
+            status: None,
+        }
+    }
+}
+
+#[cfg(feature = "hyper")]
+impl From<hyper_util::client::legacy::Error> for Error {
+    fn from(value: hyper_util::client::legacy::Error) -> Self {
+        Self::Client(value.into())
+    }
+}
@@ -4,11 +4,11 @@
 //!
 #![doc = include_utils::include_md!("README.md:description")]
 
-use bytes::Bytes;
-use http_body::Body as HttpBody;
-use http_body_util::BodyDataStream;
 use tower_layer::Layer;
 
+#[cfg(feature = "hyper")]
+#[cfg_attr(docsrs, doc(cfg(feature = "hyper")))]
+pub use crate::adapters::hyper::HyperClient;
 #[doc(inline)]
 pub use crate::error::Error;
 
@@ -18,6 +18,41 @@ pub mod error;
 /// Alias for a Result with the error type `crate::Error`.
 pub type Result<T, E = crate::Error> = std::result::Result<T, E>;
 
+#[cfg(feature = "reqwest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+/// A per-request timeout honored by the [`HttpClientService`].
+///
+/// Attach this value to the request extensions to limit the time of receiving the response
+/// headers for an individual request. If the timeout expires, the service returns the
+/// [`ClientError`] caused by the [`TimeoutError`].
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use tower_reqwest::RequestTimeout;
+///
+/// let request = http::Request::builder()
+///     .uri("http://localhost")
+///     .extension(RequestTimeout(Duration::from_secs(5)))
+///     .body(reqwest::Body::default())
+///     .unwrap();
+/// ```
+///
+/// [`ClientError`]: crate::error::ClientError
+/// [`TimeoutError`]: crate::error::TimeoutError
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestTimeout(pub std::time::Duration);
+
+/// The address of the server which sent the response.
+///
+/// The client adapters add this value to the response extensions, if the underlying
+/// connection reports its remote address. It may be absent, for example, for the responses
+/// which are not received via the HTTP connector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemoteAddr(pub std::net::SocketAddr);
+
 /// Adapter type to creating Tower HTTP services from the various clients.
 #[derive(Debug, Clone)]
 pub struct HttpClientService<S>(S);
@@ -47,12 +82,95 @@ impl<S> Layer<S> for HttpClientLayer {
 }
 
 /// Converts an arbitrary body type into the `reqwest::Body` one.
+///
+/// The body is always streamed, so the request is sent with the chunked transfer encoding.
+/// Use the [`into_reqwest_body_sized`] function for the in-memory bodies.
+#[cfg(feature = "reqwest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
 pub fn into_reqwest_body<B>(body: B) -> reqwest::Body
 where
-    B: HttpBody + Send + Sync + 'static,
+    B: http_body::Body + Send + Sync + 'static,
     B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
-    Bytes: From<B::Data>,
+    bytes::Bytes: From<B::Data>,
+{
+    let stream = http_body_util::BodyDataStream::new(body);
+    reqwest::Body::wrap_stream(stream)
+}
+
+/// Converts an arbitrary body type into the `reqwest::Body` one, keeping the in-memory
+/// bodies in memory.
+///
+/// If the body has the exact size hint and all its data frames are immediately available,
+/// like for the [`Full`] body, the data is passed to `reqwest` as [`Bytes`], so the request
+/// is sent with the `Content-Length` header. Otherwise, the already polled data is sent
+/// followed by the rest of the body stream, just like by the [`into_reqwest_body`]
+/// function.
+///
+/// [`Full`]: http_body_util::Full
+/// [`Bytes`]: bytes::Bytes
+#[cfg(feature = "reqwest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+pub fn into_reqwest_body_sized<B>(body: B) -> reqwest::Body
+where
+    B: http_body::Body + Send + Sync + 'static,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    bytes::Bytes: From<B::Data>,
+{
+    use std::task::{Context, Poll};
+
+    use futures_util::{stream, StreamExt as _, TryStreamExt as _};
+
+    if body.size_hint().exact().is_none() {
+        return into_reqwest_body(body);
+    }
+
+    let mut body = Box::pin(body);
+    let mut cx = Context::from_waker(futures_util::task::noop_waker_ref());
+    let mut head: Vec<bytes::Bytes> = Vec::new();
+    let tail = loop {
+        match body.as_mut().poll_frame(&mut cx) {
+            Poll::Ready(None) => {
+                let bytes = match <[_; 1]>::try_from(head) {
+                    Ok([single]) => single,
+                    Err(chunks) => {
+                        let mut bytes = bytes::BytesMut::new();
+                        for chunk in &chunks {
+                            bytes.extend_from_slice(chunk);
+                        }
+                        bytes.freeze()
+                    }
+                };
+                return reqwest::Body::from(bytes);
+            }
+            Poll::Ready(Some(Ok(frame))) => {
+                if let Ok(data) = frame.into_data() {
+                    head.push(bytes::Bytes::from(data));
+                }
+            }
+            Poll::Ready(Some(Err(err))) => {
+                let err: Box<dyn std::error::Error + Send + Sync> = err.into();
+                break stream::iter(Some(Err(err))).left_stream();
+            }
+            // The body will be polled again with the real waker by the stream.
+            Poll::Pending => {
+                break http_body_util::BodyDataStream::new(body)
+                    .map_ok(bytes::Bytes::from)
+                    .map_err(Into::<Box<dyn std::error::Error + Send + Sync>>::into)
+                    .right_stream()
+            }
+        }
+    };
+
+    wrap_bytes_stream(stream::iter(head.into_iter().map(Ok)).chain(tail))
+}
+
+// Keeps the `Bytes: From<B::Data>` bound out of scope, which confuses the type inference.
+#[cfg(feature = "reqwest")]
+fn wrap_bytes_stream<S>(stream: S) -> reqwest::Body
+where
+    S: futures_util::Stream<Item = Result<bytes::Bytes, Box<dyn std::error::Error + Send + Sync>>>
+        + Send
+        + 'static,
 {
-    let stream = BodyDataStream::new(body);
     reqwest::Body::wrap_stream(stream)
 }